@@ -29,6 +29,32 @@ pub fn sanitize_chunk_comprehensive(input: &str) -> String {
         .join(" ")
 }
 
+/// Splits text into word aligned chunks of at most `chunk_size` bytes
+pub fn chunk_text(content: &str, chunk_size: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current_chunk = String::new();
+
+    for word in content.split_whitespace() {
+        if current_chunk.len() + word.len() + 1 > chunk_size {
+            // If adding the next word would exceed chunk size,
+            // save current chunk and start a new one
+            if !current_chunk.is_empty() {
+                chunks.push(current_chunk.trim().to_string());
+                current_chunk.clear();
+            }
+        }
+        current_chunk.push_str(word);
+        current_chunk.push(' ');
+    }
+
+    // last chunk
+    if !current_chunk.is_empty() {
+        chunks.push(current_chunk.trim().to_string());
+    }
+
+    chunks
+}
+
 /// Zero-copy approach for better performance with large chunks
 pub fn _sanitize_chunk_cow(input: &str) -> Cow<str> {
     if input.contains('\0') { Cow::Owned(input.replace('\0', "")) } else { Cow::Borrowed(input) }
@@ -79,6 +105,13 @@ mod tests {
         println!("Clean text: {}", clean);
     }
 
+    #[test]
+    fn test_chunk_text() {
+        let chunks = chunk_text("one two three four five", 10);
+        assert_eq!(chunks, vec!["one two", "three", "four five"]);
+        assert!(chunk_text("  ", 10).is_empty());
+    }
+
     #[test]
     fn test_cow_approach() {
         let clean_text = "Hello World";
@@ -1,35 +1,595 @@
-use clap::Command;
+use anyhow::{ bail, Result };
+use clap::{ Arg, ArgAction, ArgGroup, ArgMatches, Command };
+use std::collections::HashMap;
+use std::time::Duration;
 use tracing::{ error, info };
 
 use crate::{
+    backup,
+    collection_create,
+    collection_drop,
+    collection_list,
+    docs_delete,
+    docs_list,
+    docs_show,
+    doctor,
+    export,
+    import,
     info,
     init_documents,
     init_pdf_documents,
     init_schema,
+    keys_create,
+    keys_list,
+    keys_revoke,
+    mcp,
+    metadata,
+    migrate_status,
+    migrate_up,
+    models_list,
+    models_pull,
+    models_show,
     query_pdf_documents,
     query_documents,
+    reembed,
+    restore,
+    Scope,
+    serve,
+    show_config,
+    stats,
+    tenant_create,
+    tenant_drop,
+    tenant_list,
+    hashmap_to_json_value,
+    ApiScope,
+    ConfigSource,
+    Configuration,
+    DocumentTarget,
+    Filter,
+    Identity,
+    MetadataUpdate,
+    QueryOptions,
     RagSystem,
+    UserMetaData,
+    RAG_LIST_PAGE_SIZE,
+    RAG_REEMBED_BATCH_SIZE,
 };
 
 pub struct Cli {}
 
+fn filter_arg() -> Arg {
+    Arg::new("filter")
+        .long("filter")
+        .value_name("EXPR")
+        .action(ArgAction::Append)
+        .help(
+            "Metadata filter, repeatable and combined with AND: field=value, field=a|b, field>=value, field<value, field~value (contains), field? (exists) or a JSON expression"
+        )
+}
+
+fn query_options(matches: &ArgMatches) -> Result<QueryOptions> {
+    let filters = matches
+        .get_many::<String>("filter")
+        .unwrap_or_default()
+        .map(|expression| expression.parse::<Filter>())
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(QueryOptions { filter: Filter::all(filters) })
+}
+
+fn tag_arg(name: &'static str, help: &'static str) -> Arg {
+    Arg::new(name).long(name).value_name("TAG").action(ArgAction::Append).help(help)
+}
+
+fn meta_arg() -> Arg {
+    Arg::new("meta").long("meta").value_name("KEY=VALUE").action(ArgAction::Append).help("Set a metadata attribute, repeatable")
+}
+
+// Parse repeated `--meta key=value` pairs into a JSON object
+fn meta_attributes(matches: &ArgMatches) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let mut pairs = HashMap::new();
+    for pair in matches.get_many::<String>("meta").unwrap_or_default() {
+        match pair.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                pairs.insert(key.trim().to_string(), value.trim().to_string());
+            }
+            _ => bail!("Invalid metadata '{}', expected key=value", pair),
+        }
+    }
+
+    match hashmap_to_json_value(pairs) {
+        serde_json::Value::Object(attributes) => Ok(attributes),
+        _ => Ok(serde_json::Map::new()),
+    }
+}
+
+fn owner_arg(help: &'static str) -> Arg {
+    Arg::new("owner").long("owner").value_name("USER").help(help)
+}
+
+fn user_metadata(matches: &ArgMatches) -> Result<UserMetaData> {
+    Ok(UserMetaData {
+        tags: matches.get_many::<String>("tag").unwrap_or_default().cloned().collect(),
+        attributes: meta_attributes(matches)?,
+        owner: matches.get_one::<String>("owner").cloned(),
+        groups: matches.get_many::<String>("group").unwrap_or_default().cloned().collect(),
+    })
+}
+
+// Identity to act as, from the global `--as-user` and `--as-group` flags
+fn identity(matches: &ArgMatches) -> Option<Identity> {
+    matches.get_one::<String>("as-user").map(|user| Identity {
+        user: user.clone(),
+        groups: matches.get_many::<String>("as-group").unwrap_or_default().cloned().collect(),
+    })
+}
+
+fn target_args(command: Command) -> Command {
+    command
+        .arg(Arg::new("id").long("id").value_name("ID").help("Document chunk record id"))
+        .arg(Arg::new("source").long("source").value_name("FILE").help("All chunks of a source file"))
+        .group(ArgGroup::new("target").args(["id", "source"]).required(true))
+}
+
+fn document_target(matches: &ArgMatches) -> Result<DocumentTarget> {
+    match (matches.get_one::<String>("id"), matches.get_one::<String>("source")) {
+        (Some(id), _) => Ok(DocumentTarget::Id(id.clone())),
+        (_, Some(source)) => Ok(DocumentTarget::Source(source.clone())),
+        _ => bail!("Either --id or --source is required"),
+    }
+}
+
+fn metadata_update(matches: &ArgMatches) -> Result<(DocumentTarget, MetadataUpdate)> {
+    let target = document_target(matches)?;
+    let update = MetadataUpdate {
+        add_tags: matches.get_many::<String>("tag").unwrap_or_default().cloned().collect(),
+        remove_tags: matches.get_many::<String>("untag").unwrap_or_default().cloned().collect(),
+        set_attributes: meta_attributes(matches)?,
+        unset_attributes: matches.get_many::<String>("unset").unwrap_or_default().cloned().collect(),
+        add_groups: matches.get_many::<String>("group").unwrap_or_default().cloned().collect(),
+        remove_groups: matches.get_many::<String>("ungroup").unwrap_or_default().cloned().collect(),
+        owner: match (matches.get_one::<String>("owner"), matches.get_flag("clear-owner")) {
+            (Some(owner), _) => Some(Some(owner.clone())),
+            (None, true) => Some(None),
+            (None, false) => None,
+        },
+    };
+
+    Ok((target, update))
+}
+
 impl Cli {
-    pub async fn run(rag: &RagSystem) {
-        let mut cmd = Command::new("rigrag")
+    pub fn command() -> Command {
+        Command::new("rigrag")
             .version("1.0")
             .about("Rig Rag Rust PoC CLI")
+            .arg(
+                Arg::new("config")
+                    .long("config")
+                    .value_name("FILE")
+                    .global(true)
+                    .help("Configuration file, defaults to rigrag.toml when present")
+            )
+            .arg(
+                Arg::new("profile")
+                    .long("profile")
+                    .value_name("NAME")
+                    .global(true)
+                    .help("Configuration file profile, overrides APP_PROFILE")
+            )
+            .arg(
+                Arg::new("set")
+                    .long("set")
+                    .value_name("KEY=VALUE")
+                    .action(ArgAction::Append)
+                    .global(true)
+                    .help("Override a setting, e.g. --set ollama_url=http://localhost:11434, repeatable")
+            )
+            .arg(
+                Arg::new("collection")
+                    .long("collection")
+                    .value_name("NAME")
+                    .global(true)
+                    .help("Knowledge base collection to ingest into and query, defaults to `documents`")
+            )
+            .arg(
+                Arg::new("tenant")
+                    .long("tenant")
+                    .value_name("NAME")
+                    .global(true)
+                    .help("Tenant whose namespace/database to use, for `keys create` the tenant the key is bound to")
+            )
+            .arg(
+                Arg::new("as-user")
+                    .long("as-user")
+                    .value_name("USER")
+                    .global(true)
+                    .help("Act as this user, documents are restricted by their owner and groups")
+            )
+            .arg(
+                Arg::new("as-group")
+                    .long("as-group")
+                    .value_name("GROUP")
+                    .action(ArgAction::Append)
+                    .global(true)
+                    .requires("as-user")
+                    .help("Group of the `--as-user` identity, repeatable")
+            )
+            .subcommand(Command::new("config").about("Show the effective configuration, secrets redacted"))
+            .subcommand(Command::new("doctor").about("Check database, schema, Ollama and models, printing a report with hints"))
+            .subcommand(
+                Command::new("models")
+                    .about("Manage Ollama models")
+                    .subcommand_required(true)
+                    .subcommand(Command::new("list").about("List local models, marking the configured ones"))
+                    .subcommand(
+                        Command::new("pull")
+                            .about("Pull models with progress, defaults to the configured embedding and generation models")
+                            .arg(Arg::new("model").action(ArgAction::Append))
+                    )
+                    .subcommand(
+                        Command::new("show")
+                            .about("Show model details and context length")
+                            .arg(Arg::new("model").help("Model, defaults to the configured generation model"))
+                    )
+            )
             .subcommand(Command::new("info").about("Knowledge base info"))
+            .subcommand(
+                Command::new("stats")
+                    .about("Knowledge base statistics for capacity planning")
+                    .arg(Arg::new("format").long("format").value_name("FORMAT").value_parser(["text", "json"]).help("Output format, defaults to text"))
+            )
             .subcommand(Command::new("init-schema").about("Init vectorDB Schema"))
-            .subcommand(Command::new("init-documents").about("Init sample documents"))
-            .subcommand(Command::new("init-pdf-documents").about("Init PDF sample documents"))
             .subcommand(
-                Command::new("query-documents").about("Query knowledge base sample documents")
+                Command::new("migrate")
+                    .about("Manage vectorDB schema migrations")
+                    .subcommand_required(true)
+                    .subcommand(Command::new("up").about("Apply pending migrations"))
+                    .subcommand(Command::new("status").about("Show applied and pending migrations"))
+            )
+            .subcommand(
+                Command::new("collection")
+                    .about("Manage knowledge base collections")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("create")
+                            .about("Create a collection with its own table and embedding model")
+                            .arg(Arg::new("name").required(true))
+                            .arg(Arg::new("model").long("model").value_name("MODEL").help("Embedding model, defaults to the configured one"))
+                            .arg(
+                                Arg::new("dimension")
+                                    .long("dimension")
+                                    .value_name("DIMENSION")
+                                    .value_parser(clap::value_parser!(u16))
+                                    .help("Embedding dimension, defaults to the configured one")
+                            )
+                    )
+                    .subcommand(Command::new("list").about("List collections"))
+                    .subcommand(
+                        Command::new("drop")
+                            .about("Drop a collection and all of its documents")
+                            .arg(Arg::new("name").required(true))
+                    )
+            )
+            .subcommand(
+                Command::new("tenant")
+                    .about("Manage tenants, each with its own namespace/database")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("create")
+                            .about("Create a tenant and initialize its schema")
+                            .arg(Arg::new("name").required(true))
+                            .arg(Arg::new("namespace").long("namespace").value_name("NS").help("Namespace, defaults to the tenant name"))
+                            .arg(Arg::new("database").long("database").value_name("DB").help("Database, defaults to the configured one"))
+                    )
+                    .subcommand(Command::new("list").about("List tenants"))
+                    .subcommand(
+                        Command::new("drop")
+                            .about("Drop a tenant and its database")
+                            .arg(Arg::new("name").required(true))
+                    )
+            )
+            .subcommand(
+                Command::new("init-documents")
+                    .about("Init sample documents")
+                    .arg(tag_arg("tag", "Tag applied to every ingested document, repeatable"))
+                    .arg(meta_arg())
+                    .arg(owner_arg("Owner allowed to see the documents, omit with --group for public documents"))
+                    .arg(tag_arg("group", "Group allowed to see the documents, repeatable"))
+            )
+            .subcommand(
+                Command::new("init-pdf-documents")
+                    .about("Init PDF sample documents, `<file>.meta.json` sidecars are merged in")
+                    .arg(tag_arg("tag", "Tag applied to every ingested document, repeatable"))
+                    .arg(meta_arg())
+                    .arg(owner_arg("Owner allowed to see the documents, omit with --group for public documents"))
+                    .arg(tag_arg("group", "Group allowed to see the documents, repeatable"))
+            )
+            .subcommand(
+                Command::new("reembed")
+                    .about("Re-embed the collection with another embedding model and swap it in once complete")
+                    .arg(Arg::new("model").long("model").value_name("MODEL").required(true).help("New embedding model"))
+                    .arg(
+                        Arg::new("dimension")
+                            .long("dimension")
+                            .value_name("DIMENSION")
+                            .required(true)
+                            .value_parser(clap::value_parser!(u16))
+                            .help("Embedding dimension of the new model")
+                    )
+                    .arg(
+                        Arg::new("batch-size")
+                            .long("batch-size")
+                            .value_name("SIZE")
+                            .value_parser(clap::value_parser!(usize))
+                            .help("Chunks embedded per request, defaults to 32")
+                    )
+                    .arg(Arg::new("force").long("force").action(ArgAction::SetTrue).help("Take over from an interrupted re-embed of the collection"))
+            )
+            .subcommand(
+                Command::new("export")
+                    .about("Export the collection with embeddings as JSON Lines")
+                    .arg(Arg::new("file").required(true).help("Output .jsonl file"))
+            )
+            .subcommand(
+                Command::new("import")
+                    .about("Import a JSON Lines export without re-embedding")
+                    .arg(Arg::new("file").required(true).help("Input .jsonl file"))
+            )
+            .subcommand(
+                Command::new("backup")
+                    .about("Back up the configured namespace/database with a manifest")
+                    .arg(Arg::new("file").required(true).help("Output .surql file, the manifest is written next to it"))
+            )
+            .subcommand(
+                Command::new("restore")
+                    .about("Restore a backup after validating its manifest")
+                    .arg(Arg::new("file").required(true).help("Backup .surql file"))
+                    .arg(Arg::new("force").long("force").action(ArgAction::SetTrue).help("Restore even if collections already hold documents"))
+            )
+            .subcommand(
+                Command::new("docs")
+                    .about("Manage stored documents")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("list")
+                            .about("List sources with their chunk counts")
+                            .arg(Arg::new("page").long("page").value_name("PAGE").value_parser(clap::value_parser!(usize)).help("Page number, starting at 1"))
+                            .arg(
+                                Arg::new("page-size")
+                                    .long("page-size")
+                                    .value_name("SIZE")
+                                    .value_parser(clap::value_parser!(usize))
+                                    .help("Sources per page, defaults to 20")
+                            )
+                    )
+                    .subcommand(
+                        Command::new("show")
+                            .about("Show a document chunk by id, or all chunks of a source")
+                            .arg(Arg::new("id-or-source").required(true))
+                    )
+                    .subcommand(target_args(Command::new("delete").about("Delete a document chunk by id, or all chunks of a source")))
+            )
+            .subcommand(
+                target_args(Command::new("metadata").about("Edit tags and metadata of stored documents"))
+                    .arg(tag_arg("tag", "Tag to add, repeatable"))
+                    .arg(tag_arg("untag", "Tag to remove, repeatable"))
+                    .arg(meta_arg())
+                    .arg(Arg::new("unset").long("unset").value_name("KEY").action(ArgAction::Append).help("Metadata attribute to remove, repeatable"))
+                    .arg(owner_arg("Set the owner allowed to see the documents"))
+                    .arg(Arg::new("clear-owner").long("clear-owner").action(ArgAction::SetTrue).conflicts_with("owner").help("Remove the owner"))
+                    .arg(tag_arg("group", "Group to allow, repeatable"))
+                    .arg(tag_arg("ungroup", "Group to disallow, repeatable"))
+            )
+            .subcommand(
+                Command::new("serve")
+                    .about("Serve the knowledge base over an HTTP API")
+                    .arg(Arg::new("bind").long("bind").value_name("ADDR").help("Listen address, defaults to APP_HTTP_SERVER_URI"))
             )
             .subcommand(
-                Command::new("query-pdf-documents").about("Query knowledge base PDF documents")
-            );
+                Command::new("keys")
+                    .about("Manage API keys of the HTTP API")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("create")
+                            .about("Create an API key, its secret is shown once")
+                            .arg(Arg::new("name").required(true))
+                            .arg(
+                                Arg::new("scope")
+                                    .long("scope")
+                                    .value_name("SCOPE")
+                                    .action(ArgAction::Append)
+                                    .value_parser(["query", "ingest"])
+                                    .help("Granted scope, repeatable: query (read-only) or ingest (ingest and delete), defaults to query")
+                            )
+                            .arg(tag_arg("group", "Document group the key may see, repeatable"))
+                            .arg(
+                                Arg::new("requests-per-day")
+                                    .long("requests-per-day")
+                                    .value_name("COUNT")
+                                    .value_parser(clap::value_parser!(usize))
+                                    .help("Daily request quota, unlimited by default")
+                            )
+                            .arg(
+                                Arg::new("tokens-per-day")
+                                    .long("tokens-per-day")
+                                    .value_name("COUNT")
+                                    .value_parser(clap::value_parser!(usize))
+                                    .help("Daily generated token quota, unlimited by default")
+                            )
+                    )
+                    .subcommand(Command::new("list").about("List API keys with their usage"))
+                    .subcommand(
+                        Command::new("revoke")
+                            .about("Revoke an API key")
+                            .arg(Arg::new("name").required(true))
+                    )
+            )
+            .subcommand(Command::new("mcp").about("Serve knowledge base tools over the Model Context Protocol on stdio"))
+            .subcommand(
+                Command::new("query-documents")
+                    .about("Query knowledge base sample documents")
+                    .arg(filter_arg())
+            )
+            .subcommand(
+                Command::new("query-pdf-documents")
+                    .about("Query knowledge base PDF documents")
+                    .arg(filter_arg())
+            )
+    }
+
+    /// Configuration file, profile and overrides given on the command line
+    pub fn config_source(matches: &ArgMatches) -> Result<ConfigSource> {
+        let overrides = matches
+            .get_many::<String>("set")
+            .unwrap_or_default()
+            .map(|setting| match setting.split_once('=') {
+                Some((key, value)) => Ok((key.trim().to_lowercase(), value.to_string())),
+                None => bail!("Invalid --set '{}', expected KEY=VALUE", setting),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ConfigSource {
+            file: matches.get_one::<String>("config").map(Into::into),
+            profile: matches.get_one::<String>("profile").cloned(),
+            overrides,
+        })
+    }
+
+    /// Tenant and collection the command runs on, verifying their embedding model only for commands that embed.
+    /// Tenants and API keys are managed in the configured database, `--tenant` binds new keys
+    pub fn scope(matches: &ArgMatches) -> Scope {
+        if matches!(matches.subcommand_name(), Some("tenant" | "keys")) {
+            return Scope { verify: false, ..Default::default() };
+        }
+        // Re-embedding moves a collection to another model and probes that one itself
+        let embeds = matches!(
+            matches.subcommand_name(),
+            Some("init-schema" | "init-documents" | "init-pdf-documents" | "query-documents" | "query-pdf-documents" | "serve" | "mcp")
+        );
+
+        Scope {
+            tenant: matches.get_one::<String>("tenant").cloned(),
+            collection: matches.get_one::<String>("collection").cloned(),
+            verify: embeds,
+        }
+    }
+
+    /// Runs commands that need no database connection, returns false for the others
+    pub async fn run_standalone(matches: &ArgMatches, cfg: &Configuration) -> bool {
+        match matches.subcommand() {
+            Some(("config", _sub_matches)) => {
+                show_config(cfg);
+                true
+            }
+            Some(("models", sub_matches)) => {
+                match sub_matches.subcommand() {
+                    Some(("list", _list_matches)) =>
+                        match models_list(cfg).await {
+                            Ok(_) => info!("Finished models list"),
+                            Err(e) => error!("{}", e),
+                        }
+                    Some(("pull", pull_matches)) => {
+                        let models: Vec<String> = pull_matches.get_many::<String>("model").unwrap_or_default().cloned().collect();
+                        match models_pull(cfg, &models).await {
+                            Ok(_) => info!("Finished models pull"),
+                            Err(e) => error!("{}", e),
+                        }
+                    }
+                    Some(("show", show_matches)) => {
+                        let model = show_matches.get_one::<String>("model").unwrap_or(&cfg.ollama_generation_model);
+                        match models_show(cfg, model).await {
+                            Ok(_) => info!("Finished models show"),
+                            Err(e) => error!("{}", e),
+                        }
+                    }
+                    _ => unreachable!("subcommand required"),
+                }
+                true
+            }
+            Some(("doctor", _sub_matches)) => {
+                match doctor(cfg).await {
+                    Ok(_) => info!("Finished doctor"),
+                    Err(e) => error!("{}", e),
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs a command on `rag`, opened on the tenant and collection of `Cli::scope`
+    pub async fn run(matches: &ArgMatches, rag: &RagSystem, cfg: &Configuration) {
+        let mut cmd = Self::command();
+
+        let tenant = matches.get_one::<String>("tenant");
+
+        // Tenants and API keys are managed in the configured database, `--tenant` binds new keys
+        match matches.subcommand() {
+            Some(("tenant", sub_matches)) => {
+                match sub_matches.subcommand() {
+                    Some(("create", create_matches)) => {
+                        let name = create_matches.get_one::<String>("name").unwrap();
+                        let namespace = create_matches.get_one::<String>("namespace").map(String::as_str);
+                        let database = create_matches.get_one::<String>("database").map(String::as_str);
+                        match tenant_create(rag, name, namespace, database).await {
+                            Ok(_) => info!("Finished tenant creation"),
+                            Err(e) => error!("{}", e),
+                        }
+                    }
+                    Some(("list", _list_matches)) =>
+                        match tenant_list(rag).await {
+                            Ok(_) => info!("Finished tenant list"),
+                            Err(e) => error!("{}", e),
+                        }
+                    Some(("drop", drop_matches)) =>
+                        match tenant_drop(rag, drop_matches.get_one::<String>("name").unwrap()).await {
+                            Ok(_) => info!("Finished tenant drop"),
+                            Err(e) => error!("{}", e),
+                        }
+                    _ => unreachable!("subcommand required"),
+                }
+                return;
+            }
+            Some(("keys", sub_matches)) => {
+                match sub_matches.subcommand() {
+                    Some(("create", create_matches)) => {
+                        let scopes = create_matches
+                            .get_many::<String>("scope")
+                            .map(|scopes| scopes.map(|scope| scope.parse::<ApiScope>()).collect::<Result<Vec<_>>>())
+                            .unwrap_or_else(|| Ok(vec![ApiScope::Query]));
+                        match scopes {
+                            Ok(scopes) => {
+                                let requests_per_day = create_matches.get_one::<usize>("requests-per-day").copied();
+                                let tokens_per_day = create_matches.get_one::<usize>("tokens-per-day").copied();
+                                let groups = create_matches.get_many::<String>("group").unwrap_or_default().cloned().collect();
+                                let name = create_matches.get_one::<String>("name").unwrap();
+                                match keys_create(rag, name, scopes, groups, tenant.cloned(), requests_per_day, tokens_per_day).await {
+                                    Ok(_) => info!("Finished API key creation"),
+                                    Err(e) => error!("{}", e),
+                                }
+                            }
+                            Err(e) => error!("{}", e),
+                        }
+                    }
+                    Some(("list", _list_matches)) =>
+                        match keys_list(rag).await {
+                            Ok(_) => info!("Finished API key list"),
+                            Err(e) => error!("{}", e),
+                        }
+                    Some(("revoke", revoke_matches)) =>
+                        match keys_revoke(rag, revoke_matches.get_one::<String>("name").unwrap()).await {
+                            Ok(_) => info!("Finished API key revocation"),
+                            Err(e) => error!("{}", e),
+                        }
+                    _ => unreachable!("subcommand required"),
+                }
+                return;
+            }
+            _ => {}
+        }
 
-        let matches = cmd.clone().get_matches();
+        let rag = &rag.clone().with_identity(identity(matches));
 
         match matches.subcommand() {
             Some(("info", _sub_matches)) =>
@@ -37,29 +597,171 @@ impl Cli {
                     Ok(_) => info!("Finished knowledge base info"),
                     Err(e) => error!("{}", e),
                 }
+            Some(("stats", sub_matches)) =>
+                match stats(rag, sub_matches.get_one::<String>("format").map(String::as_str).unwrap_or("text")).await {
+                    Ok(_) => info!("Finished knowledge base stats"),
+                    Err(e) => error!("{}", e),
+                }
             Some(("init-schema", _sub_matches)) =>
                 match init_schema(rag).await {
                     Ok(_) => info!("Finished vectorDB schema initialization"),
                     Err(e) => error!("{}", e),
                 }
-            Some(("init-documents", _sub_matches)) =>
-                match init_documents(rag).await {
-                    Ok(_) => info!("Finished sample documents initialization"),
+            Some(("migrate", sub_matches)) =>
+                match sub_matches.subcommand() {
+                    Some(("up", _up_matches)) =>
+                        match migrate_up(rag).await {
+                            Ok(_) => info!("Finished schema migrations"),
+                            Err(e) => error!("{}", e),
+                        }
+                    Some(("status", _status_matches)) =>
+                        match migrate_status(rag).await {
+                            Ok(_) => info!("Finished schema migrations status"),
+                            Err(e) => error!("{}", e),
+                        }
+                    _ => unreachable!("subcommand required"),
+                }
+            Some(("collection", sub_matches)) =>
+                match sub_matches.subcommand() {
+                    Some(("create", create_matches)) => {
+                        let name = create_matches.get_one::<String>("name").unwrap();
+                        let model = create_matches.get_one::<String>("model").cloned().unwrap_or_else(|| rag.embedding_model.clone());
+                        let dimension = create_matches.get_one::<u16>("dimension").copied().unwrap_or(rag.embedding_model_dimension);
+                        match collection_create(rag, name, &model, dimension).await {
+                            Ok(_) => info!("Finished collection creation"),
+                            Err(e) => error!("{}", e),
+                        }
+                    }
+                    Some(("list", _list_matches)) =>
+                        match collection_list(rag).await {
+                            Ok(_) => info!("Finished collection list"),
+                            Err(e) => error!("{}", e),
+                        }
+                    Some(("drop", drop_matches)) =>
+                        match collection_drop(rag, drop_matches.get_one::<String>("name").unwrap()).await {
+                            Ok(_) => info!("Finished collection drop"),
+                            Err(e) => error!("{}", e),
+                        }
+                    _ => unreachable!("subcommand required"),
+                }
+            Some(("init-documents", sub_matches)) =>
+                match user_metadata(sub_matches) {
+                    Ok(user_metadata) =>
+                        match init_documents(rag, &user_metadata).await {
+                            Ok(_) => info!("Finished sample documents initialization"),
+                            Err(e) => error!("{}", e),
+                        }
+                    Err(e) => error!("{}", e),
+                }
+            Some(("init-pdf-documents", sub_matches)) =>
+                match user_metadata(sub_matches) {
+                    Ok(user_metadata) =>
+                        match init_pdf_documents(rag, &user_metadata).await {
+                            Ok(_) => info!("Finished PDF sample documents initialization"),
+                            Err(e) => error!("{}", e),
+                        }
+                    Err(e) => error!("{}", e),
+                }
+            Some(("reembed", sub_matches)) => {
+                let model = sub_matches.get_one::<String>("model").unwrap();
+                let dimension = *sub_matches.get_one::<u16>("dimension").unwrap();
+                let batch_size = sub_matches.get_one::<usize>("batch-size").copied().unwrap_or(RAG_REEMBED_BATCH_SIZE);
+                match reembed(rag, model, dimension, batch_size, sub_matches.get_flag("force")).await {
+                    Ok(_) => info!("Finished collection re-embedding"),
+                    Err(e) => error!("{}", e),
+                }
+            }
+            Some(("export", sub_matches)) =>
+                match export(rag, sub_matches.get_one::<String>("file").unwrap()).await {
+                    Ok(_) => info!("Finished knowledge base export"),
+                    Err(e) => error!("{}", e),
+                }
+            Some(("import", sub_matches)) =>
+                match import(rag, sub_matches.get_one::<String>("file").unwrap()).await {
+                    Ok(_) => info!("Finished knowledge base import"),
+                    Err(e) => error!("{}", e),
+                }
+            Some(("backup", sub_matches)) =>
+                match backup(rag, sub_matches.get_one::<String>("file").unwrap()).await {
+                    Ok(_) => info!("Finished knowledge base backup"),
                     Err(e) => error!("{}", e),
                 }
-            Some(("init-pdf-documents", _sub_matches)) =>
-                match init_pdf_documents(rag).await {
-                    Ok(_) => info!("Finished PDF sample documents initialization"),
+            Some(("restore", sub_matches)) =>
+                match restore(rag, sub_matches.get_one::<String>("file").unwrap(), sub_matches.get_flag("force")).await {
+                    Ok(_) => info!("Finished knowledge base restore"),
                     Err(e) => error!("{}", e),
                 }
-            Some(("query-documents", _sub_matches)) =>
-                match query_documents(rag).await {
-                    Ok(_) => info!("Finished query knowledge base sample documents"),
+            Some(("docs", sub_matches)) =>
+                match sub_matches.subcommand() {
+                    Some(("list", list_matches)) => {
+                        let page = list_matches.get_one::<usize>("page").copied().unwrap_or(1);
+                        let page_size = list_matches.get_one::<usize>("page-size").copied().unwrap_or(RAG_LIST_PAGE_SIZE);
+                        match docs_list(rag, page, page_size).await {
+                            Ok(_) => info!("Finished documents list"),
+                            Err(e) => error!("{}", e),
+                        }
+                    }
+                    Some(("show", show_matches)) =>
+                        match docs_show(rag, show_matches.get_one::<String>("id-or-source").unwrap()).await {
+                            Ok(_) => info!("Finished documents show"),
+                            Err(e) => error!("{}", e),
+                        }
+                    Some(("delete", delete_matches)) =>
+                        match document_target(delete_matches) {
+                            Ok(target) =>
+                                match docs_delete(rag, &target).await {
+                                    Ok(_) => info!("Finished documents delete"),
+                                    Err(e) => error!("{}", e),
+                                }
+                            Err(e) => error!("{}", e),
+                        }
+                    _ => unreachable!("subcommand required"),
+                }
+            Some(("metadata", sub_matches)) =>
+                match metadata_update(sub_matches) {
+                    Ok((target, update)) =>
+                        match metadata(rag, &target, &update).await {
+                            Ok(_) => info!("Finished documents metadata update"),
+                            Err(e) => error!("{}", e),
+                        }
+                    Err(e) => error!("{}", e),
+                }
+            Some(("serve", sub_matches)) => {
+                if cfg.ollama_health_interval_secs > 0 {
+                    let interval = Duration::from_secs(cfg.ollama_health_interval_secs);
+                    if rag.embedder.servers() > 1 {
+                        rag.embedder.spawn_health_checks(interval);
+                    }
+                    if rag.generator.servers() > 1 && !rag.generator.shares_servers(&rag.embedder) {
+                        rag.generator.spawn_health_checks(interval);
+                    }
+                }
+                match serve(rag, sub_matches.get_one::<String>("bind").unwrap_or(&cfg.http_server_uri), cfg.api_auth).await {
+                    Ok(_) => info!("Finished HTTP API"),
+                    Err(e) => error!("{}", e),
+                }
+            }
+            Some(("mcp", _sub_matches)) =>
+                match mcp(rag).await {
+                    Ok(_) => info!("Finished MCP server"),
+                    Err(e) => error!("{}", e),
+                }
+            Some(("query-documents", sub_matches)) =>
+                match query_options(sub_matches) {
+                    Ok(options) =>
+                        match query_documents(rag, &options).await {
+                            Ok(_) => info!("Finished query knowledge base sample documents"),
+                            Err(e) => error!("{}", e),
+                        }
                     Err(e) => error!("{}", e),
                 }
-            Some(("query-pdf-documents", _sub_matches)) =>
-                match query_pdf_documents(rag).await {
-                    Ok(_) => info!("Finished query knowledge base pdf documents"),
+            Some(("query-pdf-documents", sub_matches)) =>
+                match query_options(sub_matches) {
+                    Ok(options) =>
+                        match query_pdf_documents(rag, &options).await {
+                            Ok(_) => info!("Finished query knowledge base pdf documents"),
+                            Err(e) => error!("{}", e),
+                        }
                     Err(e) => error!("{}", e),
                 }
             _ => {
@@ -71,3 +773,25 @@ impl Cli {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_scope() {
+        let scope = |args: &[&str]| Cli::scope(&Cli::command().try_get_matches_from(args).unwrap());
+
+        let query = scope(&["rigrag", "--tenant", "acme", "--collection", "hr", "query-documents"]);
+        assert_eq!((query.tenant.as_deref(), query.collection.as_deref(), query.verify), (Some("acme"), Some("hr"), true));
+        assert!(!scope(&["rigrag", "--collection", "hr", "stats"]).verify);
+        assert!(!scope(&["rigrag", "reembed", "--model", "bge-m3", "--dimension", "1024"]).verify);
+        let keys = scope(&["rigrag", "--tenant", "acme", "keys", "list"]);
+        assert_eq!((keys.tenant, keys.verify), (None, false));
+    }
+}
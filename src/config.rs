@@ -1,4 +1,12 @@
-use serde::Deserialize;
+use anyhow::{ bail, Context, Result };
+use serde::{ Deserialize, Serialize };
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{ Path, PathBuf };
+
+use crate::rag_system::ollama;
+use crate::{ Api, Balance, DbAuth, Reranker, RAG_CONFIG_FILE, RAG_RERANK_CONCURRENCY, RAG_RERANK_TOP_N };
 
 // Environment variables defaults
 fn default_surreal_db_url() -> String {
@@ -9,8 +17,8 @@ fn default_surreal_db_user() -> String {
     "root".to_string()
 }
 
-fn default_surreal_db_pass() -> String {
-    "root".to_string()
+fn default_surreal_db_pass() -> Secret {
+    Secret("root".to_string())
 }
 
 fn default_surreal_db_ns() -> String {
@@ -21,10 +29,26 @@ fn default_surreal_db_db() -> String {
     "documents".to_string()
 }
 
+fn default_surreal_db_auth() -> String {
+    "root".to_string()
+}
+
+fn default_surreal_db_access() -> String {
+    "rag_user".to_string()
+}
+
 fn default_ollama_url() -> String {
     "http://localhost:11434".to_string()
 }
 
+fn default_ollama_balance() -> String {
+    "round-robin".to_string()
+}
+
+fn default_ollama_health_interval_secs() -> u64 {
+    30
+}
+
 fn default_ollama_embedding_model() -> String {
     "nomic-embed-text".to_string()
 }
@@ -37,9 +61,109 @@ fn default_ollama_generation_model() -> String {
     "llama3.2".to_string()
 }
 
+fn default_ollama_auto_pull() -> bool {
+    false
+}
+
+fn default_ollama_connect_timeout_secs() -> u64 {
+    5
+}
+
+fn default_ollama_request_timeout_secs() -> u64 {
+    300
+}
+
+fn default_ollama_max_retries() -> u32 {
+    3
+}
+
+fn default_ollama_retry_base_delay_ms() -> u64 {
+    250
+}
+
+fn default_ollama_retry_max_delay_ms() -> u64 {
+    10_000
+}
+
+fn default_ollama_breaker_threshold() -> u32 {
+    5
+}
+
+fn default_ollama_breaker_cooldown_secs() -> u64 {
+    30
+}
+
+fn default_backend() -> String {
+    "ollama".to_string()
+}
+
+fn default_openai_url() -> String {
+    "http://localhost:8080/v1".to_string()
+}
+
+fn default_openai_embedding_model_dimension() -> u16 {
+    768
+}
+
+fn default_reranker() -> String {
+    "none".to_string()
+}
+
+fn default_reranker_url() -> String {
+    "http://localhost:8080/v1/rerank".to_string()
+}
+
+fn default_reranker_model() -> String {
+    "bge-reranker-v2-m3".to_string()
+}
+
+fn default_reranker_top_n() -> usize {
+    RAG_RERANK_TOP_N
+}
+
+fn default_reranker_concurrency() -> usize {
+    RAG_RERANK_CONCURRENCY
+}
+
+fn default_auto_migrate() -> bool {
+    false
+}
+
+fn default_http_server_uri() -> String {
+    "0.0.0.0:8080".to_string()
+}
+
+fn default_api_auth() -> bool {
+    true
+}
+
+// Secret value, redacted in debug output
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_empty() { "\"\"" } else { "\"***\"" })
+    }
+}
+
 // Data structures for Environment variables
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Configuration {
+    // Profile of the configuration file applied on top of its top-level settings
+    #[serde(default)]
+    pub profile: Option<String>,
     #[serde(default = "default_surreal_db_url")]
     pub surreal_db_url: String,
     #[serde(default = "default_surreal_db_user")]
@@ -49,13 +173,461 @@ pub struct Configuration {
     #[serde(default = "default_surreal_db_db")]
     pub surreal_db_db: String,
     #[serde(default = "default_surreal_db_pass")]
-    pub surreal_db_pass: String,
+    pub surreal_db_pass: Secret,
+    // root | namespace | database | record | token
+    #[serde(default = "default_surreal_db_auth")]
+    pub surreal_db_auth: String,
+    // Record access method used with `record` auth
+    #[serde(default = "default_surreal_db_access")]
+    pub surreal_db_access: String,
+    // JWT used with `token` auth
+    #[serde(default)]
+    pub surreal_db_token: Secret,
+    // PEM file with CA certificates trusted for `wss://`/`https://` URLs
+    #[serde(default)]
+    pub surreal_db_ca_cert: Option<String>,
     #[serde(default = "default_ollama_url")]
     pub ollama_url: String,
+    // Pool of Ollama servers, `url` or `url=weight` comma separated, replaces `ollama_url` when set
+    #[serde(default)]
+    pub ollama_urls: String,
+    // round-robin | least-loaded
+    #[serde(default = "default_ollama_balance")]
+    pub ollama_balance: String,
+    // Interval of the background health checks of `serve`, 0 disables them
+    #[serde(default = "default_ollama_health_interval_secs")]
+    pub ollama_health_interval_secs: u64,
     #[serde(default = "default_ollama_embedding_model")]
     pub ollama_embedding_model: String,
     #[serde(default = "default_ollama_embedding_model_dimension")]
     pub ollama_embedding_model_dimension: u16,
     #[serde(default = "default_ollama_generation_model")]
     pub ollama_generation_model: String,
+    // Pull missing embedding/generation models on startup
+    #[serde(default = "default_ollama_auto_pull")]
+    pub ollama_auto_pull: bool,
+    #[serde(default = "default_ollama_connect_timeout_secs")]
+    pub ollama_connect_timeout_secs: u64,
+    // Deadline of a whole request, or between reads of a streamed response
+    #[serde(default = "default_ollama_request_timeout_secs")]
+    pub ollama_request_timeout_secs: u64,
+    // Retries of connection errors, timeouts, 429 and 5xx responses, with exponential backoff and jitter
+    #[serde(default = "default_ollama_max_retries")]
+    pub ollama_max_retries: u32,
+    #[serde(default = "default_ollama_retry_base_delay_ms")]
+    pub ollama_retry_base_delay_ms: u64,
+    #[serde(default = "default_ollama_retry_max_delay_ms")]
+    pub ollama_retry_max_delay_ms: u64,
+    // Consecutive failed requests to a server opening its circuit breaker, 0 disables it
+    #[serde(default = "default_ollama_breaker_threshold")]
+    pub ollama_breaker_threshold: u32,
+    #[serde(default = "default_ollama_breaker_cooldown_secs")]
+    pub ollama_breaker_cooldown_secs: u64,
+    // ollama | openai, OpenAI-compatible servers (llama.cpp, vLLM, LM Studio) get the `openai_*_model` names
+    #[serde(default = "default_backend")]
+    pub embedding_backend: String,
+    #[serde(default = "default_backend")]
+    pub generation_backend: String,
+    // Base URL of the OpenAI-compatible API, including `/v1`
+    #[serde(default = "default_openai_url")]
+    pub openai_url: String,
+    // Embedding server when it is not `openai_url`, e.g. a second llama.cpp server started with `--embedding`
+    #[serde(default)]
+    pub openai_embedding_url: Option<String>,
+    #[serde(default)]
+    pub openai_api_key: Secret,
+    // Models requested from the OpenAI-compatible servers, required by the roles served by them
+    #[serde(default)]
+    pub openai_embedding_model: String,
+    #[serde(default = "default_openai_embedding_model_dimension")]
+    pub openai_embedding_model_dimension: u16,
+    #[serde(default)]
+    pub openai_generation_model: String,
+    // Tokens of the OpenAI-compatible generation model, prompts are not budgeted when unset
+    #[serde(default)]
+    pub openai_context_window: Option<usize>,
+    // none | llm | endpoint
+    #[serde(default = "default_reranker")]
+    pub reranker: String,
+    #[serde(default = "default_reranker_url")]
+    pub reranker_url: String,
+    #[serde(default = "default_reranker_model")]
+    pub reranker_model: String,
+    #[serde(default = "default_reranker_top_n")]
+    pub reranker_top_n: usize,
+    // Passages the llm reranker scores in parallel
+    #[serde(default = "default_reranker_concurrency")]
+    pub reranker_concurrency: usize,
+    // Apply pending schema migrations on startup
+    #[serde(default = "default_auto_migrate")]
+    pub auto_migrate: bool,
+    // Bind address of the `serve` HTTP API
+    #[serde(default = "default_http_server_uri")]
+    pub http_server_uri: String,
+    // Require API keys on the HTTP API, disable for local development only
+    #[serde(default = "default_api_auth")]
+    pub api_auth: bool,
+}
+
+// Where the configuration is read from, layered as: defaults, file, file profile, `APP_*` environment, CLI overrides
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSource {
+    /// Configuration file, `rigrag.toml` in the working directory is used when present
+    pub file: Option<PathBuf>,
+    /// Profile to apply, overrides `APP_PROFILE` and the file's `profile` key
+    pub profile: Option<String>,
+    /// `key=value` settings from the command line
+    pub overrides: Vec<(String, String)>,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        envy::from_iter(Vec::<(String, String)>::new()).expect("every setting has a default")
+    }
+}
+
+impl Configuration {
+    /// Setting names, as used in the configuration file and (upper cased, `APP_` prefixed) in the environment
+    pub fn keys() -> Vec<String> {
+        match serde_json::to_value(Configuration::default()) {
+            Ok(serde_json::Value::Object(settings)) => settings.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Loads and validates the layered configuration
+    pub fn load(source: &ConfigSource) -> Result<Self> {
+        Self::load_from(source, std::env::vars())
+    }
+
+    fn load_from(source: &ConfigSource, env: impl Iterator<Item = (String, String)>) -> Result<Self> {
+        let keys = Self::keys();
+        let env: BTreeMap<String, String> = env
+            .filter_map(|(key, value)| key.strip_prefix("APP_").map(|key| (key.to_lowercase(), value)))
+            .collect();
+        for key in env.keys() {
+            if !keys.contains(key) {
+                bail!("Unknown setting 'APP_{}' in the environment, expected one of: {}", key.to_uppercase(), keys.join(", "));
+            }
+        }
+        for (key, _) in &source.overrides {
+            if !keys.contains(key) {
+                bail!("Unknown setting '{}' in --set, expected one of: {}", key, keys.join(", "));
+            }
+        }
+
+        let path = match &source.file {
+            Some(path) => Some(path.clone()),
+            None => Some(PathBuf::from(RAG_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+        let file = path.as_deref().map(ConfigFile::read).transpose()?;
+
+        let profile = source.profile
+            .clone()
+            .or_else(|| env.get("profile").cloned())
+            .or_else(|| file.as_ref().and_then(|file| file.settings.get("profile").cloned()));
+
+        let mut settings = BTreeMap::new();
+        if let Some(file) = &file {
+            settings.extend(file.settings.clone());
+            if let Some(profile) = &profile {
+                settings.extend(file.profile(profile)?);
+            }
+        } else if let Some(profile) = &profile {
+            bail!("Profile '{}' requested but no configuration file found, create {} or pass --config", profile, RAG_CONFIG_FILE);
+        }
+        settings.extend(env);
+        settings.extend(source.overrides.iter().cloned());
+        if let Some(profile) = profile {
+            settings.insert("profile".to_string(), profile);
+        }
+
+        let cfg: Configuration = envy::from_iter(settings).context("Invalid configuration")?;
+        cfg.validate()?;
+
+        Ok(cfg)
+    }
+
+    /// Configured embedding and generation models served by Ollama
+    pub fn ollama_models(&self) -> Vec<String> {
+        [(&self.embedding_backend, &self.ollama_embedding_model), (&self.generation_backend, &self.ollama_generation_model)]
+            .into_iter()
+            .filter(|(backend, _)| matches!(Api::from_name(backend), Ok(Api::Ollama)))
+            .map(|(_, model)| model.clone())
+            .collect()
+    }
+
+    /// Embedding model and dimension of the configured embedding backend
+    pub fn embedding_model(&self) -> (&str, u16) {
+        match Api::from_name(&self.embedding_backend) {
+            Ok(Api::OpenAi) => (&self.openai_embedding_model, self.openai_embedding_model_dimension),
+            _ => (&self.ollama_embedding_model, self.ollama_embedding_model_dimension),
+        }
+    }
+
+    /// Generation model of the configured generation backend
+    pub fn generation_model(&self) -> &str {
+        match Api::from_name(&self.generation_backend) {
+            Ok(Api::OpenAi) => &self.openai_generation_model,
+            _ => &self.ollama_generation_model,
+        }
+    }
+
+    /// Checks the settings together, reporting every problem at once
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+
+        if !self.ollama_url.starts_with("http://") && !self.ollama_url.starts_with("https://") {
+            problems.push(format!("ollama_url '{}' must start with http:// or https://", self.ollama_url));
+        }
+        if let Err(e) = ollama::endpoints_from_config(self) {
+            problems.push(e.to_string());
+        }
+        if let Err(e) = Balance::from_config(self) {
+            problems.push(e.to_string());
+        }
+        for (setting, backend) in [("embedding_backend", &self.embedding_backend), ("generation_backend", &self.generation_backend)] {
+            if let Err(e) = Api::from_name(backend) {
+                problems.push(format!("{}: {}", setting, e));
+            }
+        }
+        for (setting, url) in [("openai_url", Some(&self.openai_url)), ("openai_embedding_url", self.openai_embedding_url.as_ref())] {
+            if let Some(url) = url.filter(|url| !url.starts_with("http://") && !url.starts_with("https://")) {
+                problems.push(format!("{} '{}' must start with http:// or https://", setting, url));
+            }
+        }
+        if let Some((scheme, _)) = self.surreal_db_url.split_once("://") {
+            if !["ws", "wss", "http", "https"].contains(&scheme) {
+                problems.push(format!("surreal_db_url scheme '{}' must be one of: ws, wss, http, https", scheme));
+            }
+        }
+        if self.ollama_embedding_model.is_empty() {
+            problems.push("ollama_embedding_model must not be empty".to_string());
+        }
+        if self.ollama_generation_model.is_empty() {
+            problems.push("ollama_generation_model must not be empty".to_string());
+        }
+        if self.ollama_embedding_model_dimension == 0 {
+            problems.push("ollama_embedding_model_dimension must be greater than 0".to_string());
+        }
+        if matches!(Api::from_name(&self.embedding_backend), Ok(Api::OpenAi)) {
+            if self.openai_embedding_model.is_empty() {
+                problems.push("openai_embedding_model must be set when embedding_backend is openai".to_string());
+            }
+            if self.openai_embedding_model_dimension == 0 {
+                problems.push("openai_embedding_model_dimension must be greater than 0".to_string());
+            }
+        }
+        if matches!(Api::from_name(&self.generation_backend), Ok(Api::OpenAi)) && self.openai_generation_model.is_empty() {
+            problems.push("openai_generation_model must be set when generation_backend is openai".to_string());
+        }
+        if let Err(e) = Reranker::from_config(self) {
+            problems.push(e.to_string());
+        }
+        if self.ollama_connect_timeout_secs == 0 || self.ollama_request_timeout_secs == 0 {
+            problems.push("ollama_connect_timeout_secs and ollama_request_timeout_secs must be greater than 0".to_string());
+        }
+        if self.ollama_retry_base_delay_ms > self.ollama_retry_max_delay_ms {
+            problems.push("ollama_retry_base_delay_ms must not exceed ollama_retry_max_delay_ms".to_string());
+        }
+        if self.reranker_top_n == 0 {
+            problems.push("reranker_top_n must be greater than 0".to_string());
+        }
+        if self.reranker_concurrency == 0 {
+            problems.push("reranker_concurrency must be greater than 0".to_string());
+        }
+        if let Err(e) = DbAuth::from_config(self) {
+            problems.push(e.to_string());
+        }
+        if let Some(path) = &self.surreal_db_ca_cert {
+            if !Path::new(path).is_file() {
+                problems.push(format!("surreal_db_ca_cert '{}' does not exist", path));
+            }
+        }
+        if self.http_server_uri.parse::<SocketAddr>().is_err() {
+            problems.push(format!("http_server_uri '{}' must be an address like 0.0.0.0:8080", self.http_server_uri));
+        }
+
+        if !problems.is_empty() {
+            let profile = self.profile.as_ref().map(|profile| format!(" (profile '{}')", profile)).unwrap_or_default();
+            bail!("Invalid configuration{}:\n  - {}", profile, problems.join("\n  - "));
+        }
+
+        Ok(())
+    }
+}
+
+// Parsed configuration file: top-level settings and `[profiles.<name>]` tables
+struct ConfigFile {
+    path: PathBuf,
+    settings: BTreeMap<String, String>,
+    profiles: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl ConfigFile {
+    fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read configuration file {}", path.display()))?;
+        let mut table: toml::Table = content.parse().with_context(|| format!("Invalid configuration file {}", path.display()))?;
+
+        let mut profiles = BTreeMap::new();
+        match table.remove("profiles") {
+            None => {}
+            Some(toml::Value::Table(tables)) => {
+                for (name, value) in tables {
+                    let toml::Value::Table(profile) = value else {
+                        bail!("{}: profiles.{} must be a table", path.display(), name);
+                    };
+                    let settings = settings(path, &format!("profiles.{}.", name), profile)?;
+                    if settings.contains_key("profile") {
+                        bail!("{}: profiles.{} cannot select another profile", path.display(), name);
+                    }
+                    profiles.insert(name, settings);
+                }
+            }
+            Some(_) => bail!("{}: profiles must be a table of [profiles.<name>] sections", path.display()),
+        }
+
+        Ok(ConfigFile {
+            path: path.to_path_buf(),
+            settings: settings(path, "", table)?,
+            profiles,
+        })
+    }
+
+    fn profile(&self, name: &str) -> Result<BTreeMap<String, String>> {
+        match self.profiles.get(name) {
+            Some(settings) => Ok(settings.clone()),
+            None => {
+                let available = self.profiles.keys().cloned().collect::<Vec<_>>();
+                bail!(
+                    "Unknown profile '{}' in {}, available: {}",
+                    name,
+                    self.path.display(),
+                    if available.is_empty() { "none".to_string() } else { available.join(", ") }
+                )
+            }
+        }
+    }
+}
+
+/// Flattens a table of known settings into the string form read by `envy`
+fn settings(path: &Path, prefix: &str, table: toml::Table) -> Result<BTreeMap<String, String>> {
+    let keys = Configuration::keys();
+    table
+        .into_iter()
+        .map(|(key, value)| {
+            if !keys.contains(&key) {
+                bail!("{}: unknown setting '{}{}'", path.display(), prefix, key);
+            }
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Integer(value) => value.to_string(),
+                toml::Value::Float(value) => value.to_string(),
+                toml::Value::Boolean(value) => value.to_string(),
+                _ => bail!("{}: '{}{}' must be a string, number or boolean", path.display(), prefix, key),
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_file(name: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rigrag-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_layered_configuration() {
+        let path = write_file(
+            "layers",
+            r#"
+            profile = "local"
+            ollama_generation_model = "llama3.2"
+            surreal_db_pass = "file-secret"
+
+            [profiles.local]
+            ollama_url = "http://localhost:11434"
+
+            [profiles.gpu-box-104]
+            ollama_url = "http://192.168.90.104:11434"
+            ollama_embedding_model_dimension = 1024
+            "#
+        );
+        let mut source = ConfigSource { file: Some(path.clone()), ..Default::default() };
+
+        let cfg = Configuration::load_from(&source, std::iter::empty()).unwrap();
+        assert_eq!(cfg.profile.as_deref(), Some("local"));
+        assert_eq!(cfg.ollama_url, "http://localhost:11434");
+        assert!(!format!("{:?}", cfg).contains("file-secret"));
+
+        let env = vec![
+            ("APP_PROFILE".to_string(), "gpu-box-104".to_string()),
+            ("APP_OLLAMA_GENERATION_MODEL".to_string(), "qwen3".to_string())
+        ];
+        source.overrides = vec![("ollama_embedding_model_dimension".to_string(), "768".to_string())];
+        let cfg = Configuration::load_from(&source, env.into_iter()).unwrap();
+        assert_eq!(cfg.ollama_url, "http://192.168.90.104:11434");
+        assert_eq!(cfg.ollama_generation_model, "qwen3");
+        assert_eq!(cfg.ollama_embedding_model_dimension, 768);
+
+        source.profile = Some("gpu-box-133".to_string());
+        let error = Configuration::load_from(&source, std::iter::empty()).unwrap_err().to_string();
+        assert!(error.contains("available: gpu-box-104, local"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_validate() {
+        let path = write_file("invalid", "ollama_urll = \"http://localhost:11434\"");
+        let source = ConfigSource { file: Some(path.clone()), ..Default::default() };
+        assert!(Configuration::load_from(&source, std::iter::empty()).unwrap_err().to_string().contains("unknown setting 'ollama_urll'"));
+        std::fs::remove_file(path).unwrap();
+
+        let env = vec![("APP_OLLAMA_GENERATON_MODEL".to_string(), "qwen3".to_string())];
+        let error = Configuration::load_from(&ConfigSource::default(), env.into_iter()).unwrap_err().to_string();
+        assert!(error.contains("Unknown setting 'APP_OLLAMA_GENERATON_MODEL' in the environment"));
+
+        let source = ConfigSource { overrides: vec![("ollama_url".to_string(), "localhost".to_string()), ("reranker".to_string(), "bm25".to_string())], ..Default::default() };
+        let error = Configuration::load_from(&source, std::iter::empty()).unwrap_err().to_string();
+        assert!(error.contains("ollama_url 'localhost' must start with http:// or https://"));
+        assert!(error.contains("Unknown reranker 'bm25'"));
+
+        let source = ConfigSource {
+            overrides: vec![("generation_backend".to_string(), "vllm".to_string()), ("openai_url".to_string(), "localhost:8080/v1".to_string())],
+            ..Default::default()
+        };
+        let error = Configuration::load_from(&source, std::iter::empty()).unwrap_err().to_string();
+        assert!(error.contains("generation_backend: Unknown backend 'vllm'"));
+        assert!(error.contains("openai_url 'localhost:8080/v1' must start with http:// or https://"));
+    }
+
+    #[test]
+    fn test_backend_models() {
+        let set = |settings: &[(&str, &str)]| ConfigSource {
+            overrides: settings
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+
+        let error = Configuration::load_from(&set(&[("generation_backend", "openai")]), std::iter::empty()).unwrap_err().to_string();
+        assert!(error.contains("openai_generation_model must be set when generation_backend is openai"));
+
+        let source = set(&[("generation_backend", "openai"), ("openai_generation_model", "qwen3-8b")]);
+        let cfg = Configuration::load_from(&source, std::iter::empty()).unwrap();
+        assert_eq!(cfg.generation_model(), "qwen3-8b");
+        assert_eq!(cfg.embedding_model(), ("nomic-embed-text", 768));
+
+        let source = set(&[("embedding_backend", "openai"), ("openai_embedding_model", "bge-m3"), ("openai_embedding_model_dimension", "1024")]);
+        let cfg = Configuration::load_from(&source, std::iter::empty()).unwrap();
+        assert_eq!(cfg.embedding_model(), ("bge-m3", 1024));
+        assert_eq!(cfg.generation_model(), "llama3.2");
+    }
 }
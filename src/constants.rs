@@ -1,2 +1,17 @@
 pub const RAG_CHUNK_SIZE: usize = 2000;
-pub const RAG_RETRIEVE_SIMILAR_DOCUMENT_CHUNKS: usize = 10;
\ No newline at end of file
+pub const RAG_RETRIEVE_SIMILAR_DOCUMENT_CHUNKS: usize = 10;
+pub const RAG_RERANK_TOP_N: usize = 4;
+pub const RAG_RERANK_CONCURRENCY: usize = 4;
+pub const RAG_REEMBED_BATCH_SIZE: usize = 32;
+pub const RAG_EXPORT_FORMAT_VERSION: u32 = 1;
+pub const RAG_EXPORT_BATCH_SIZE: usize = 256;
+pub const RAG_BACKUP_FORMAT_VERSION: u32 = 1;
+pub const RAG_LIST_PAGE_SIZE: usize = 20;
+pub const RAG_DEFAULT_COLLECTION: &str = "documents";
+pub const RAG_UPLOAD_LIMIT: usize = 64 * 1024 * 1024;
+pub const RAG_NO_DOCUMENTS_ANSWER: &str = "No relevant documents found in the knowledge base.";
+pub const RAG_OLLAMA_DEFAULT_NUM_CTX: usize = 4096;
+pub const RAG_ANSWER_TOKEN_RESERVE: usize = 512;
+pub const RAG_CHARS_PER_TOKEN: usize = 4;
+pub const RAG_CONFIG_FILE: &str = "rigrag.toml";
+pub const RAG_MCP_PROTOCOL_VERSION: &str = "2024-11-05";
@@ -2,14 +2,59 @@ mod chunk_processor;
 mod cli;
 mod config;
 mod constants;
+mod mcp;
 mod rag_system;
+mod server;
 mod sub_commands;
 mod util;
 
 pub use chunk_processor::*;
 pub use cli::Cli;
-pub use config::Configuration;
+pub use config::{ ConfigSource, Configuration, Secret };
 pub use constants::*;
-pub use rag_system::RagSystem;
+pub use rag_system::{
+    latest_version,
+    Api,
+    ApiKey,
+    ApiScope,
+    BackupManifest,
+    Balance,
+    Collection,
+    CompiledFilter,
+    DbAuth,
+    DbConnection,
+    DocumentMetaData,
+    DocumentOrder,
+    DocumentTarget,
+    ExportChunk,
+    ExportHeader,
+    ExportRecord,
+    Filter,
+    Generation,
+    GenerationChunk,
+    Identity,
+    KnowledgeBaseStats,
+    ListOptions,
+    MetadataUpdate,
+    Migration,
+    MigrationStatus,
+    MIGRATIONS,
+    ModelBackend,
+    ModelInfo,
+    OllamaClient,
+    OllamaModel,
+    OllamaPullProgress,
+    OpenAiClient,
+    QueryOptions,
+    RagAnswer,
+    RagSystem,
+    Reranker,
+    Scope,
+    SourceReference,
+    SourceStats,
+    SourceSummary,
+    Tenant,
+    UserMetaData,
+};
 pub use sub_commands::*;
 pub use util::*;
\ No newline at end of file
@@ -1,38 +1,77 @@
 use anyhow::Result;
-use rigrag::{Cli, Configuration, RagSystem};
+use rigrag::{pull_missing_models, Api, Cli, Configuration, DbConnection, ModelBackend, OllamaClient, RagSystem, Reranker};
+use tracing::{debug, info, warn};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Load environment variables from .env file.
-    dotenvy::dotenv().expect("Failed to read .env file");
-
-    // Init Environment Configuration
-    let env_cfg = envy::prefixed("APP_")
-        .from_env::<Configuration>()
-        .expect("Error loading configuration...");
-    println!("{:#?}", env_cfg);
-
-    // info!("Starting RAG system with Ollama and SurrealDB");
-
-    // Initialize RAG system
-    let rag = RagSystem::new(
-        &env_cfg.surreal_db_url,
-        &env_cfg.surreal_db_user,
-        &env_cfg.surreal_db_pass,
-        &env_cfg.surreal_db_ns,
-        &env_cfg.surreal_db_db,
-        &env_cfg.ollama_url,
-        &env_cfg.ollama_embedding_model,
-        env_cfg.ollama_embedding_model_dimension,
-        &env_cfg.ollama_generation_model,
+    let matches = Cli::command().get_matches();
+
+    // Load environment variables from an optional .env file.
+    if let Err(e) = dotenvy::dotenv() {
+        if !e.not_found() {
+            return Err(e.into());
+        }
+    }
+
+    // Init layered Configuration: file, profile, environment and CLI overrides
+    let cfg = Configuration::load(&Cli::config_source(&matches)?)?;
+
+    // Initialize logging, on stderr to keep command output (e.g. `stats --format json`) clean
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+    debug!("{:#?}", cfg);
+
+    if Cli::run_standalone(&matches, &cfg).await {
+        return Ok(());
+    }
+
+    // Ollama client with timeouts, retries and circuit breaker, balanced over the configured servers
+    let ollama = OllamaClient::from_config(&cfg)?;
+
+    // Embedding and generation backends, Ollama or OpenAI-compatible servers
+    let embedder = ModelBackend::embedder(&ollama, &cfg)?;
+    let generator = ModelBackend::generator(&ollama, &cfg)?;
+    let uses_ollama = embedder.api() == Api::Ollama || generator.api() == Api::Ollama;
+
+    // Leave unreachable servers of a pool out until their circuit breaker cooldown elapsed
+    if uses_ollama && ollama.endpoints().len() > 1 {
+        for (url, result) in ollama.check_health().await {
+            match result {
+                Ok(version) => info!("Ollama {} at {} is up", version, url),
+                Err(e) => warn!("Ollama at {} is down, skipping it for now: {:#}", url, e),
+            }
+        }
+    }
+
+    // Pull missing models before the embedding model is verified
+    if cfg.ollama_auto_pull && uses_ollama {
+        pull_missing_models(&ollama, &cfg).await?;
+    }
+
+    // Initialize RAG system on the tenant and collection of the command
+    let generates_with_openai = generator.api() == Api::OpenAi;
+    let (embedding_model, embedding_model_dimension) = cfg.embedding_model();
+    let mut rag = RagSystem::new_scoped(
+        DbConnection::from_config(&cfg)?,
+        embedder,
+        generator,
+        embedding_model,
+        embedding_model_dimension,
+        cfg.generation_model(),
+        &Cli::scope(&matches),
     )
-    .await?;
+    .await?
+    .with_reranker(Reranker::from_config(&cfg)?);
+    if generates_with_openai {
+        rag = rag.with_context_window(cfg.openai_context_window);
+    }
 
-    // Initialize logging
-    tracing_subscriber::fmt::init();
+    // Apply pending schema migrations
+    if cfg.auto_migrate {
+        rag.migrate_up().await?;
+    }
 
     // Run command Line App
-    Cli::run(&rag).await;
+    Cli::run(&matches, &rag, &cfg).await;
 
     Ok(())
 }
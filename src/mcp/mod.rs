@@ -0,0 +1,226 @@
+mod structures;
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde_json::{ json, Value };
+use tokio::io::{ AsyncBufReadExt, AsyncWriteExt, BufReader };
+use tracing::{ info, warn };
+
+use crate::{
+    chunk_text,
+    record_key,
+    sanitize_chunk_comprehensive,
+    DocumentMetaData,
+    Filter,
+    RagSystem,
+    UserMetaData,
+    RAG_CHUNK_SIZE,
+    RAG_MCP_PROTOCOL_VERSION,
+    RAG_RETRIEVE_SIMILAR_DOCUMENT_CHUNKS,
+};
+use structures::{
+    GetDocumentArguments,
+    IngestTextArguments,
+    JsonRpcRequest,
+    JsonRpcResponse,
+    SearchArguments,
+    ToolCall,
+    ToolDocument,
+};
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Tools advertised by `tools/list`
+fn tools() -> Value {
+    json!([
+        {
+            "name": "search_knowledge_base",
+            "description": "Semantic search over the knowledge base, returns the most similar document chunks",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Search query" },
+                    "top_k": { "type": "integer", "minimum": 1, "description": "Number of chunks to return, defaults to 10" },
+                    "filter": {
+                        "description": "Metadata filter: field=value, field=a|b, field>=value, field~value, field? or a JSON filter, a list is combined with AND",
+                        "type": ["string", "array", "object"]
+                    }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "get_document",
+            "description": "Get a document chunk with its metadata by id",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "Document chunk id" }
+                },
+                "required": ["id"]
+            }
+        },
+        {
+            "name": "ingest_text",
+            "description": "Chunk, embed and store a text in the knowledge base",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Text to ingest" },
+                    "source": { "type": "string", "description": "Source name recorded in the chunk metadata" },
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                    "attributes": { "type": "object" },
+                    "owner": { "type": "string", "description": "Owner allowed to see the document, omit with groups for a public document" },
+                    "groups": { "type": "array", "items": { "type": "string" }, "description": "Groups allowed to see the document" }
+                },
+                "required": ["text", "source"]
+            }
+        }
+    ])
+}
+
+fn arguments<T: DeserializeOwned>(arguments: Value) -> Result<T> {
+    Ok(serde_json::from_value(arguments)?)
+}
+
+/// Runs a tool, returning its text output
+async fn call_tool(rag: &RagSystem, call: ToolCall) -> Result<String> {
+    // Follow a collection re-embedded while serving
+    let rag = &rag.refresh().await?;
+    match call.name.as_str() {
+        "search_knowledge_base" => {
+            let args: SearchArguments = arguments(call.arguments)?;
+            let filter = Filter::from_value(args.filter)?;
+            let top_k = args.top_k.unwrap_or(RAG_RETRIEVE_SIMILAR_DOCUMENT_CHUNKS).max(1);
+            let documents = rag
+                .retrieve_similar(&args.query, top_k, filter.as_ref()).await?
+                .into_iter()
+                .map(|doc| ToolDocument { id: doc.id.id.to_raw(), content: doc.content, metadata: doc.metadata, created_at: doc.created_at })
+                .collect::<Vec<_>>();
+            Ok(serde_json::to_string_pretty(&documents)?)
+        }
+        "get_document" => {
+            let args: GetDocumentArguments = arguments(call.arguments)?;
+            let doc = rag.get_document(record_key(&args.id)).await?.ok_or_else(|| anyhow::anyhow!("Document '{}' not found", args.id))?;
+            Ok(serde_json::to_string_pretty(&(ToolDocument { id: doc.id.id.to_raw(), content: doc.content, metadata: doc.metadata, created_at: doc.created_at }))?)
+        }
+        "ingest_text" => {
+            let args: IngestTextArguments = arguments(call.arguments)?;
+            let user_metadata = UserMetaData { tags: args.tags, attributes: args.attributes, owner: args.owner, groups: args.groups };
+            let documents = chunk_text(&args.text, RAG_CHUNK_SIZE)
+                .iter()
+                .enumerate()
+                .map(|(i, chunk)| (sanitize_chunk_comprehensive(chunk), DocumentMetaData::new(i, &args.source, &user_metadata)))
+                .collect::<Vec<_>>();
+            if documents.is_empty() {
+                anyhow::bail!("No content to ingest");
+            }
+            let ids = rag
+                .store_documents(documents).await?
+                .iter()
+                .map(|id| record_key(id).to_string())
+                .collect::<Vec<_>>();
+            Ok(serde_json::to_string_pretty(&json!({ "source": args.source, "chunks": ids.len(), "ids": ids }))?)
+        }
+        other => anyhow::bail!("Unknown tool '{}'", other),
+    }
+}
+
+/// Handles one request, notifications return `None`
+async fn handle(rag: &RagSystem, request: JsonRpcRequest) -> Option<JsonRpcResponse> {
+    let id = request.id?;
+    let response = match request.method.as_str() {
+        "initialize" => {
+            let protocol_version = request.params
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or(RAG_MCP_PROTOCOL_VERSION);
+            JsonRpcResponse::result(
+                id,
+                json!({
+                    "protocolVersion": protocol_version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "rigrag", "version": env!("CARGO_PKG_VERSION") }
+                })
+            )
+        }
+        "ping" => JsonRpcResponse::result(id, json!({})),
+        "tools/list" => JsonRpcResponse::result(id, json!({ "tools": tools() })),
+        "tools/call" =>
+            match serde_json::from_value::<ToolCall>(request.params) {
+                Ok(call) if tools().as_array().is_some_and(|tools| tools.iter().any(|tool| tool["name"] == call.name)) => {
+                    info!("MCP tool call: {}", call.name);
+                    // Tool failures are reported to the model as tool results, not protocol errors
+                    let (text, is_error) = match call_tool(rag, call).await {
+                        Ok(text) => (text, false),
+                        Err(e) => (e.to_string(), true),
+                    };
+                    JsonRpcResponse::result(id, json!({ "content": [{ "type": "text", "text": text }], "isError": is_error }))
+                }
+                Ok(call) => JsonRpcResponse::error(id, INVALID_PARAMS, format!("Unknown tool '{}'", call.name)),
+                Err(e) => JsonRpcResponse::error(id, INVALID_PARAMS, e),
+            }
+        other => JsonRpcResponse::error(id, METHOD_NOT_FOUND, format!("Method '{}' not found", other)),
+    };
+
+    Some(response)
+}
+
+/// Serves MCP over stdio until stdin is closed, stdout carries protocol messages only
+pub async fn serve_stdio(rag: RagSystem) -> Result<()> {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    info!("MCP server ready on stdio");
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<JsonRpcRequest>(&line) {
+            Ok(request) => handle(&rag, request).await,
+            Err(e) => {
+                warn!("Invalid MCP message: {}", e);
+                Some(JsonRpcResponse::error(Value::Null, PARSE_ERROR, e))
+            }
+        };
+        if let Some(response) = response {
+            let mut message = serde_json::to_vec(&response)?;
+            message.push(b'\n');
+            stdout.write_all(&message).await?;
+            stdout.flush().await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tools_have_schemas() {
+        let tools = tools();
+        let names = tools
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| {
+                assert_eq!(tool["inputSchema"]["type"], "object");
+                tool["name"].as_str().unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["search_knowledge_base", "get_document", "ingest_text"]);
+    }
+
+    #[test]
+    fn test_notification_has_no_id() {
+        let request: JsonRpcRequest = serde_json::from_str(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#).unwrap();
+        assert!(request.id.is_none());
+        let response = serde_json::to_value(JsonRpcResponse::error(json!(1), METHOD_NOT_FOUND, "nope")).unwrap();
+        assert_eq!(response, json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32601, "message": "nope" } }));
+    }
+}
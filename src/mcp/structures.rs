@@ -0,0 +1,87 @@
+use serde::{ Deserialize, Serialize };
+use serde_json::{ Map, Value };
+
+use crate::DocumentMetaData;
+
+// JSON-RPC 2.0 messages, one per line over stdio
+#[derive(Debug, Deserialize)]
+pub struct JsonRpcRequest {
+    // Absent for notifications, which get no response
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonRpcResponse {
+    pub jsonrpc: &'static str,
+    pub id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonRpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl JsonRpcResponse {
+    pub fn result(id: Value, result: Value) -> Self {
+        JsonRpcResponse { jsonrpc: "2.0", id, result: Some(result), error: None }
+    }
+
+    pub fn error(id: Value, code: i64, message: impl ToString) -> Self {
+        JsonRpcResponse { jsonrpc: "2.0", id, result: None, error: Some(JsonRpcError { code, message: message.to_string() }) }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+// Tool arguments
+#[derive(Debug, Deserialize)]
+pub struct SearchArguments {
+    pub query: String,
+    #[serde(default)]
+    pub top_k: Option<usize>,
+    // Filter expression string, list of expressions or JSON filter tree
+    #[serde(default)]
+    pub filter: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetDocumentArguments {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IngestTextArguments {
+    pub text: String,
+    pub source: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+// Document chunk returned by tools, without its embedding
+#[derive(Debug, Serialize)]
+pub struct ToolDocument {
+    pub id: String,
+    pub content: String,
+    pub metadata: DocumentMetaData,
+    pub created_at: String,
+}
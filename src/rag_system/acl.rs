@@ -0,0 +1,68 @@
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::filter::CompiledFilter;
+
+/// Visibility rule, mirrored by the table `PERMISSIONS` for record users: a document is public
+/// without owner and groups, otherwise visible to its owner and members of its groups
+pub const ACL_CONDITION: &str = "(metadata.owner = NONE AND metadata.groups = []) OR metadata.owner = $acl_user OR metadata.groups CONTAINSANY $acl_groups";
+
+// Caller identity that restricts which documents are visible
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Identity {
+    pub user: String,
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+impl Identity {
+    /// Mandatory WHERE condition for documents visible to this identity
+    pub fn condition(&self) -> CompiledFilter {
+        CompiledFilter {
+            condition: ACL_CONDITION.to_string(),
+            params: BTreeMap::from([
+                ("acl_user".to_string(), Value::String(self.user.clone())),
+                (
+                    "acl_groups".to_string(),
+                    Value::Array(
+                        self.groups
+                            .iter()
+                            .map(|group| Value::String(group.clone()))
+                            .collect()
+                    ),
+                ),
+            ]),
+        }
+    }
+}
+
+/// Joins an optional filter and an optional identity into one `AND ...` clause with its parameters
+pub fn restrict(filter: Option<CompiledFilter>, identity: Option<&Identity>) -> CompiledFilter {
+    let mut restricted = CompiledFilter::default();
+    let mut conditions = Vec::new();
+    for compiled in [filter, identity.map(Identity::condition)].into_iter().flatten() {
+        conditions.push(format!("AND ({})", compiled.condition));
+        restricted.params.extend(compiled.params);
+    }
+    restricted.condition = conditions.join(" ");
+    restricted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_restrict() {
+        assert_eq!(restrict(None, None), CompiledFilter::default());
+
+        let identity = Identity { user: "frontend".to_string(), groups: vec!["engineering".to_string()] };
+        let filter = CompiledFilter { condition: "metadata.source = $filter_0".to_string(), params: BTreeMap::from([("filter_0".to_string(), json!("a.pdf"))]) };
+        let restricted = restrict(Some(filter), Some(&identity));
+        assert_eq!(restricted.condition, format!("AND (metadata.source = $filter_0) AND ({})", ACL_CONDITION));
+        assert_eq!(restricted.params["acl_user"], json!("frontend"));
+        assert_eq!(restricted.params["acl_groups"], json!(["engineering"]));
+    }
+}
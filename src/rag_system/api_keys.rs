@@ -0,0 +1,285 @@
+use anyhow::{ bail, Result };
+use serde::{ Deserialize, Serialize };
+use sha2::{ Digest, Sha256 };
+use surrealdb::engine::any::Any;
+use surrealdb::Surreal;
+use uuid::Uuid;
+
+use super::filter::is_identifier;
+
+// Permission granted to an API key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    /// Read-only access: query, search, stats and document reads
+    Query,
+    /// Ingest and delete documents
+    Ingest,
+}
+
+impl std::fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiScope::Query => write!(f, "query"),
+            ApiScope::Ingest => write!(f, "ingest"),
+        }
+    }
+}
+
+impl std::str::FromStr for ApiScope {
+    type Err = anyhow::Error;
+
+    fn from_str(scope: &str) -> Result<Self> {
+        match scope {
+            "query" => Ok(ApiScope::Query),
+            "ingest" => Ok(ApiScope::Ingest),
+            other => bail!("Unknown scope '{}', expected query or ingest", other),
+        }
+    }
+}
+
+// API key record, only the SHA-256 hash of the secret is stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub name: String,
+    pub key_hash: String,
+    // First characters of the secret, to recognize a key in listings
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    // Document ACL groups the key is a member of
+    #[serde(default)]
+    pub groups: Vec<String>,
+    // Tenant all requests of the key are served from, the default database when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    pub requests_per_day: Option<usize>,
+    pub tokens_per_day: Option<usize>,
+    // Usage within the current UTC day
+    pub usage_day: Option<String>,
+    pub requests: usize,
+    pub tokens: usize,
+    pub created_at: String,
+    pub revoked_at: Option<String>,
+}
+
+impl ApiKey {
+    pub fn has_scope(&self, scope: ApiScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    /// Requests and tokens used today, usage from a previous day does not count
+    pub fn usage_today(&self, today: &str) -> (usize, usize) {
+        if self.usage_day.as_deref() == Some(today) { (self.requests, self.tokens) } else { (0, 0) }
+    }
+
+    /// Name of the exceeded daily quota, if any
+    pub fn exceeded_quota(&self) -> Option<&'static str> {
+        self.exceeded_quota_on(&today())
+    }
+
+    fn exceeded_quota_on(&self, today: &str) -> Option<&'static str> {
+        let (requests, tokens) = self.usage_today(today);
+        if self.requests_per_day.is_some_and(|quota| requests >= quota) {
+            return Some("requests");
+        }
+        if self.tokens_per_day.is_some_and(|quota| tokens >= quota) {
+            return Some("tokens");
+        }
+        None
+    }
+}
+
+pub fn hash_key(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// Attempts of a request claim that keeps conflicting with concurrent claims
+const CLAIM_ATTEMPTS: usize = 32;
+
+/// Current UTC day, the window of the daily quotas
+fn today() -> String {
+    chrono::Utc::now().format("%Y-%m-%d").to_string()
+}
+
+/// Creates a key and returns it with its secret, which is not stored and only shown once
+pub async fn create(
+    db: &Surreal<Any>,
+    name: &str,
+    scopes: Vec<ApiScope>,
+    groups: Vec<String>,
+    tenant: Option<String>,
+    requests_per_day: Option<usize>,
+    tokens_per_day: Option<usize>
+) -> Result<(ApiKey, String)> {
+    if !is_identifier(name) {
+        bail!("Invalid key name '{}', use letters, digits and underscores", name);
+    }
+    if scopes.is_empty() {
+        bail!("An API key needs at least one scope");
+    }
+    if get(db, name).await?.is_some() {
+        bail!("API key '{}' already exists", name);
+    }
+
+    let secret = format!("rr_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let key = ApiKey {
+        name: name.to_string(),
+        key_hash: hash_key(&secret),
+        prefix: secret[..10].to_string(),
+        scopes,
+        groups,
+        tenant,
+        requests_per_day,
+        tokens_per_day,
+        usage_day: None,
+        requests: 0,
+        tokens: 0,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        revoked_at: None,
+    };
+    let created: Option<ApiKey> = db.create(("_api_keys", name)).content(key).await?;
+    let created = created.ok_or_else(|| anyhow::anyhow!("Failed to create API key"))?;
+
+    Ok((created, secret))
+}
+
+pub async fn get(db: &Surreal<Any>, name: &str) -> Result<Option<ApiKey>> {
+    let key: Option<ApiKey> = db.select(("_api_keys", name)).await?;
+
+    Ok(key)
+}
+
+pub async fn list(db: &Surreal<Any>) -> Result<Vec<ApiKey>> {
+    let keys: Vec<ApiKey> = db.query("SELECT * OMIT id FROM _api_keys ORDER BY name").await?.take(0)?;
+
+    Ok(keys)
+}
+
+pub async fn revoke(db: &Surreal<Any>, name: &str) -> Result<ApiKey> {
+    let key: Option<ApiKey> = db
+        .query("UPDATE type::thing('_api_keys', $name) SET revoked_at = $now WHERE revoked_at = NONE RETURN AFTER")
+        .bind(("name", name.to_string()))
+        .bind(("now", chrono::Utc::now().to_rfc3339())).await?
+        .take(0)?;
+
+    match key {
+        Some(key) => Ok(key),
+        None if get(db, name).await?.is_some() => bail!("API key '{}' is already revoked", name),
+        None => bail!("API key '{}' not found", name),
+    }
+}
+
+/// Looks up an active key by its secret
+pub async fn authenticate(db: &Surreal<Any>, secret: &str) -> Result<Option<ApiKey>> {
+    let key: Option<ApiKey> = db
+        .query("SELECT * OMIT id FROM _api_keys WHERE key_hash = $key_hash AND revoked_at = NONE LIMIT 1")
+        .bind(("key_hash", hash_key(secret))).await?
+        .take(0)?;
+
+    Ok(key)
+}
+
+/// Counts a request against today's usage if no daily quota is used up, checked and counted in one statement
+/// so concurrent requests cannot all pass the check. `None` when a quota is used up or the key was revoked
+pub async fn claim_request(db: &Surreal<Any>, name: &str) -> Result<Option<ApiKey>> {
+    let mut attempt = 1;
+    loop {
+        let result = db
+            .query(
+                "
+                UPDATE type::thing('_api_keys', $name) SET
+                    requests = IF usage_day = $day { requests + 1 } ELSE { 1 },
+                    tokens = IF usage_day = $day { tokens } ELSE { 0 },
+                    usage_day = $day
+                WHERE revoked_at = NONE
+                    AND (usage_day != $day OR requests_per_day = NONE OR requests < requests_per_day)
+                    AND (usage_day != $day OR tokens_per_day = NONE OR tokens < tokens_per_day)
+                RETURN AFTER
+                "
+            )
+            .bind(("name", name.to_string()))
+            .bind(("day", today())).await;
+        let error = match result {
+            Ok(mut response) =>
+                match response.take::<Option<ApiKey>>(0) {
+                    Ok(key) => return Ok(key),
+                    Err(e) => e,
+                }
+            Err(e) => e,
+        };
+        // Concurrent claims of a key conflict, the losers retry against the updated usage
+        if attempt >= CLAIM_ATTEMPTS || !error.to_string().contains("can be retried") {
+            return Err(error.into());
+        }
+        attempt += 1;
+    }
+}
+
+/// Adds requests and tokens to today's usage, starting over on a new day
+pub async fn record_usage(db: &Surreal<Any>, name: &str, requests: usize, tokens: usize) -> Result<()> {
+    db.query(
+        "
+        UPDATE type::thing('_api_keys', $name) SET
+            requests = IF usage_day = $day { requests + $requests } ELSE { $requests },
+            tokens = IF usage_day = $day { tokens + $tokens } ELSE { $tokens },
+            usage_day = $day
+        "
+    )
+        .bind(("name", name.to_string()))
+        .bind(("day", today()))
+        .bind(("requests", requests))
+        .bind(("tokens", tokens)).await?
+        .check()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> ApiKey {
+        ApiKey {
+            name: "frontend".to_string(),
+            key_hash: hash_key("rr_secret"),
+            prefix: "rr_secret".to_string(),
+            scopes: vec![ApiScope::Query],
+            groups: vec!["engineering".to_string()],
+            tenant: None,
+            requests_per_day: Some(2),
+            tokens_per_day: Some(100),
+            usage_day: Some("2025-07-21".to_string()),
+            requests: 1,
+            tokens: 100,
+            created_at: "2025-07-21T00:00:00Z".to_string(),
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn test_hash_key() {
+        assert_eq!(hash_key("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+
+    #[test]
+    fn test_quotas() {
+        let key = key();
+        assert!(key.has_scope(ApiScope::Query));
+        assert!(!key.has_scope(ApiScope::Ingest));
+        assert_eq!(key.exceeded_quota_on("2025-07-21"), Some("tokens"));
+        // Usage resets on a new day
+        assert_eq!(key.exceeded_quota_on("2025-07-22"), None);
+    }
+
+    #[tokio::test]
+    async fn test_claim_request_enforces_quota_under_concurrency() {
+        let db = surrealdb::engine::any::connect("mem://").await.unwrap();
+        db.use_ns("rag").use_db("documents").await.unwrap();
+        super::super::migrations::up(&db, &[]).await.unwrap();
+        create(&db, "frontend", vec![ApiScope::Query], Vec::new(), None, Some(3), None).await.unwrap();
+
+        let claims = futures::future::join_all((0..10).map(|_| claim_request(&db, "frontend"))).await;
+        assert_eq!(claims.into_iter().filter(|claim| claim.as_ref().unwrap().is_some()).count(), 3);
+        assert_eq!(get(&db, "frontend").await.unwrap().unwrap().requests, 3);
+    }
+}
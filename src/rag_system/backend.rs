@@ -0,0 +1,200 @@
+use anyhow::{ bail, Result };
+use futures::stream::BoxStream;
+use reqwest::Client;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::Configuration;
+use super::ollama::OllamaClient;
+use super::openai::OpenAiClient;
+use super::structures::{ Generation, GenerationChunk };
+
+// Protocol spoken to the model servers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Api {
+    /// Ollama's native `/api/*` endpoints
+    Ollama,
+    /// OpenAI-compatible `/embeddings` and `/chat/completions` (llama.cpp server, vLLM, LM Studio), the base URL includes `/v1`
+    OpenAi,
+}
+
+impl Api {
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "" | "ollama" => Ok(Api::Ollama),
+            "openai" => Ok(Api::OpenAi),
+            other => bail!("Unknown backend '{}', expected one of: ollama, openai", other),
+        }
+    }
+
+    /// Environment variable of a setting of this backend, e.g. `APP_OPENAI_EMBEDDING_MODEL` for `embedding_model`
+    pub fn setting(&self, name: &str) -> String {
+        let prefix = match self {
+            Api::Ollama => "OLLAMA",
+            Api::OpenAi => "OPENAI",
+        };
+        format!("APP_{}_{}", prefix, name.to_uppercase())
+    }
+}
+
+// Server answering the embedding or generation requests of a RAG system
+#[derive(Clone)]
+pub enum ModelBackend {
+    Ollama(OllamaClient),
+    OpenAi(OpenAiClient),
+}
+
+impl ModelBackend {
+    /// Backend of `embedding_backend`, Ollama requests go through the given client
+    pub fn embedder(ollama: &OllamaClient, cfg: &Configuration) -> Result<Self> {
+        match Api::from_name(&cfg.embedding_backend)? {
+            Api::Ollama => Ok(ModelBackend::Ollama(ollama.clone())),
+            Api::OpenAi => Ok(ModelBackend::OpenAi(OpenAiClient::from_config(cfg.openai_embedding_url.as_deref().unwrap_or(&cfg.openai_url), cfg)?)),
+        }
+    }
+
+    /// Backend of `generation_backend`, Ollama requests go through the given client
+    pub fn generator(ollama: &OllamaClient, cfg: &Configuration) -> Result<Self> {
+        match Api::from_name(&cfg.generation_backend)? {
+            Api::Ollama => Ok(ModelBackend::Ollama(ollama.clone())),
+            Api::OpenAi => Ok(ModelBackend::OpenAi(OpenAiClient::from_config(&cfg.openai_url, cfg)?)),
+        }
+    }
+
+    pub fn api(&self) -> Api {
+        match self {
+            ModelBackend::Ollama(_) => Api::Ollama,
+            ModelBackend::OpenAi(_) => Api::OpenAi,
+        }
+    }
+
+    /// Ollama client, for the operations only Ollama supports
+    pub fn ollama(&self) -> Option<&OllamaClient> {
+        match self {
+            ModelBackend::Ollama(ollama) => Some(ollama),
+            ModelBackend::OpenAi(_) => None,
+        }
+    }
+
+    /// Base URL of the server, comma separated URLs of an Ollama pool
+    pub fn url(&self) -> String {
+        match self {
+            ModelBackend::Ollama(ollama) => ollama.url(),
+            ModelBackend::OpenAi(openai) => openai.url(),
+        }
+    }
+
+    /// Underlying HTTP client, for calls to other local services
+    pub fn http(&self) -> &Client {
+        match self {
+            ModelBackend::Ollama(ollama) => ollama.http(),
+            ModelBackend::OpenAi(openai) => openai.http(),
+        }
+    }
+
+    /// Requests worth sending in parallel to keep every server busy
+    pub fn concurrency(&self) -> usize {
+        match self {
+            ModelBackend::Ollama(ollama) => ollama.concurrency(),
+            ModelBackend::OpenAi(_) => 1,
+        }
+    }
+
+    /// Number of servers requests are balanced over
+    pub fn servers(&self) -> usize {
+        match self {
+            ModelBackend::Ollama(ollama) => ollama.endpoints().len(),
+            ModelBackend::OpenAi(_) => 1,
+        }
+    }
+
+    /// Whether both backends send to the same servers, sharing their breakers
+    pub fn shares_servers(&self, other: &ModelBackend) -> bool {
+        match (self, other) {
+            (ModelBackend::Ollama(ollama), ModelBackend::Ollama(other)) => ollama.shares_servers(other),
+            _ => false,
+        }
+    }
+
+    /// Probes every server once, opening the breaker of the unreachable ones and closing it for the others
+    pub async fn check_health(&self) -> Vec<(String, Result<String>)> {
+        match self {
+            ModelBackend::Ollama(ollama) => ollama.check_health().await,
+            ModelBackend::OpenAi(openai) => openai.check_health().await,
+        }
+    }
+
+    /// Health checks the servers every `interval` in the background, for long running servers
+    pub fn spawn_health_checks(&self, interval: Duration) {
+        let backend = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                for (url, result) in backend.check_health().await {
+                    if let Err(e) = result {
+                        warn!("Model server at {} failed its health check: {:#}", url, e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Names of the models available on the server
+    pub async fn models(&self) -> Result<Vec<String>> {
+        match self {
+            ModelBackend::Ollama(ollama) => Ok(ollama.tags().await?.into_iter().map(|model| model.name).collect()),
+            ModelBackend::OpenAi(openai) => openai.models().await,
+        }
+    }
+
+    /// Context window of a model in tokens, OpenAI-compatible servers do not report it
+    pub async fn context_window(&self, model: &str) -> Result<Option<usize>> {
+        match self {
+            ModelBackend::Ollama(ollama) => Ok(Some(ollama.show(model).await?.context_window())),
+            ModelBackend::OpenAi(_) => Ok(None),
+        }
+    }
+
+    /// Embedding of a single text
+    pub async fn embedding(&self, model: &str, text: &str) -> Result<Vec<f32>> {
+        match self {
+            ModelBackend::Ollama(ollama) => ollama.embedding(model, text).await,
+            ModelBackend::OpenAi(openai) => openai.embedding(model, text).await,
+        }
+    }
+
+    /// Embeddings of a batch of texts
+    pub async fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        match self {
+            ModelBackend::Ollama(ollama) => ollama.embed(model, texts).await,
+            ModelBackend::OpenAi(openai) => openai.embed(model, texts).await,
+        }
+    }
+
+    /// Non streaming completion of a raw prompt
+    pub async fn generate(&self, model: &str, prompt: &str) -> Result<Generation> {
+        match self {
+            ModelBackend::Ollama(ollama) => Ok(ollama.generate(model, prompt).await?.into()),
+            ModelBackend::OpenAi(openai) => openai.chat(model, prompt).await,
+        }
+    }
+
+    /// Streamed completion of a raw prompt
+    pub async fn generate_stream(&self, model: &str, prompt: &str) -> Result<BoxStream<'static, Result<GenerationChunk>>> {
+        match self {
+            ModelBackend::Ollama(ollama) => ollama.generate_stream(model, prompt).await,
+            ModelBackend::OpenAi(openai) => openai.chat_stream(model, prompt).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setting() {
+        assert_eq!(Api::Ollama.setting("embedding_model_dimension"), "APP_OLLAMA_EMBEDDING_MODEL_DIMENSION");
+        assert_eq!(Api::OpenAi.setting("generation_model"), "APP_OPENAI_GENERATION_MODEL");
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{ bail, Context, Result };
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::CertificateDer;
+use rustls::{ ClientConfig, RootCertStore };
+use serde::Serialize;
+use std::path::{ Path, PathBuf };
+use std::sync::Arc;
+use surrealdb::engine::any::{ self, Any };
+use surrealdb::opt::auth::{ Database, Namespace, Record, Root };
+use surrealdb::opt::Config;
+use surrealdb::Surreal;
+
+use crate::Configuration;
+
+// How the application signs in to SurrealDB
+#[derive(Clone, PartialEq)]
+pub enum DbAuth {
+    /// Root user, for local development only
+    Root,
+    /// User defined on the namespace
+    Namespace,
+    /// User defined on the database
+    Database,
+    /// Record user of a record access method, username/password are passed to its SIGNIN as `$name`/`$password`.
+    /// Read-only: queries see the documents the user may select, commands that write are refused
+    Record {
+        access: String,
+    },
+    /// Pre-issued JWT, scoped by its own claims
+    Token(String),
+}
+
+impl DbAuth {
+    pub fn from_config(cfg: &Configuration) -> Result<Self> {
+        match cfg.surreal_db_auth.as_str() {
+            "" | "root" => Ok(DbAuth::Root),
+            "namespace" => Ok(DbAuth::Namespace),
+            "database" => Ok(DbAuth::Database),
+            "record" => Ok(DbAuth::Record { access: cfg.surreal_db_access.clone() }),
+            "token" => {
+                if cfg.surreal_db_token.is_empty() {
+                    bail!("Token authentication requires APP_SURREAL_DB_TOKEN");
+                }
+                Ok(DbAuth::Token(cfg.surreal_db_token.expose().to_string()))
+            }
+            other => bail!("Unknown SurrealDB auth '{}', expected one of: root, namespace, database, record, token", other),
+        }
+    }
+
+    /// Record users may only read, table permissions deny them every write
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, DbAuth::Record { .. })
+    }
+}
+
+// SIGNIN parameters of the record access method
+#[derive(Serialize)]
+struct RecordCredentials<'a> {
+    name: &'a str,
+    password: &'a str,
+}
+
+// SurrealDB connection settings, kept to open additional connections
+#[derive(Clone)]
+pub struct DbConnection {
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    pub namespace: String,
+    pub database: String,
+    pub auth: DbAuth,
+    // PEM bundle of extra CA certificates trusted for `wss://`/`https://`
+    pub ca_cert: Option<PathBuf>,
+}
+
+impl DbConnection {
+    pub fn from_config(cfg: &Configuration) -> Result<Self> {
+        Ok(DbConnection {
+            url: cfg.surreal_db_url.clone(),
+            username: cfg.surreal_db_user.clone(),
+            password: cfg.surreal_db_pass.expose().to_string(),
+            namespace: cfg.surreal_db_ns.clone(),
+            database: cfg.surreal_db_db.clone(),
+            auth: DbAuth::from_config(cfg)?,
+            ca_cert: cfg.surreal_db_ca_cert.as_ref().map(PathBuf::from),
+        })
+    }
+
+    /// Endpoint with its scheme, a plain `host:port` is reached over `ws://`
+    pub fn endpoint(&self) -> String {
+        if self.url.contains("://") { self.url.clone() } else { format!("ws://{}", self.url) }
+    }
+
+    /// HTTP endpoint of the same server, `wss://` maps to `https://`
+    pub fn http_endpoint(&self) -> String {
+        let endpoint = self.endpoint();
+        if let Some(host) = endpoint.strip_prefix("wss://") {
+            format!("https://{}", host)
+        } else if let Some(host) = endpoint.strip_prefix("ws://") {
+            format!("http://{}", host)
+        } else {
+            endpoint
+        }
+    }
+
+    /// Opens the configured endpoint, signed in and scoped to the namespace/database
+    pub async fn connect(&self) -> Result<Surreal<Any>> {
+        self.open(self.endpoint()).await
+    }
+
+    /// Opens the HTTP endpoint, export/import are only supported over HTTP
+    pub async fn connect_http(&self) -> Result<Surreal<Any>> {
+        self.open(self.http_endpoint()).await
+    }
+
+    async fn open(&self, endpoint: String) -> Result<Surreal<Any>> {
+        let mut config = Config::new();
+        if let Some(path) = &self.ca_cert {
+            config = config.rustls(tls_config(path)?);
+        }
+
+        let db = any::connect((endpoint.as_str(), config)).await.with_context(|| format!("Failed to connect to SurrealDB at {}", endpoint))?;
+        self.signin(&db).await.with_context(|| format!("SurrealDB {} sign in failed", self.auth_method()))?;
+        db.use_ns(self.namespace.as_str()).use_db(self.database.as_str()).await?;
+
+        Ok(db)
+    }
+
+    async fn signin(&self, db: &Surreal<Any>) -> Result<()> {
+        match &self.auth {
+            DbAuth::Root => {
+                db.signin(Root {
+                    username: &self.username,
+                    password: &self.password,
+                }).await?;
+            }
+            DbAuth::Namespace => {
+                db.signin(Namespace {
+                    namespace: &self.namespace,
+                    username: &self.username,
+                    password: &self.password,
+                }).await?;
+            }
+            DbAuth::Database => {
+                db.signin(Database {
+                    namespace: &self.namespace,
+                    database: &self.database,
+                    username: &self.username,
+                    password: &self.password,
+                }).await?;
+            }
+            DbAuth::Record { access } => {
+                db.signin(Record {
+                    namespace: &self.namespace,
+                    database: &self.database,
+                    access,
+                    params: RecordCredentials {
+                        name: &self.username,
+                        password: &self.password,
+                    },
+                }).await?;
+            }
+            DbAuth::Token(token) => {
+                db.authenticate(token.clone()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Auth method name without its secrets, for logs and errors
+    pub fn auth_method(&self) -> &str {
+        match &self.auth {
+            DbAuth::Root => "root",
+            DbAuth::Namespace => "namespace",
+            DbAuth::Database => "database",
+            DbAuth::Record { .. } => "record",
+            DbAuth::Token(_) => "token",
+        }
+    }
+}
+
+/// TLS client configuration trusting the public web roots and the CA certificates in a PEM file
+fn tls_config(path: &Path) -> Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+    let certificates = CertificateDer::pem_file_iter(path)
+        .with_context(|| format!("Failed to read CA certificate {}", path.display()))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("Invalid CA certificate {}", path.display()))?;
+    if certificates.is_empty() {
+        bail!("No certificate found in {}", path.display());
+    }
+    for certificate in certificates {
+        roots.add(certificate)?;
+    }
+
+    Ok(
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(url: &str) -> DbConnection {
+        DbConnection {
+            url: url.to_string(),
+            username: "rag".to_string(),
+            password: "secret".to_string(),
+            namespace: "rag".to_string(),
+            database: "documents".to_string(),
+            auth: DbAuth::Database,
+            ca_cert: None,
+        }
+    }
+
+    #[test]
+    fn test_endpoints() {
+        assert_eq!(connection("127.0.0.1:8000").endpoint(), "ws://127.0.0.1:8000");
+        assert_eq!(connection("127.0.0.1:8000").http_endpoint(), "http://127.0.0.1:8000");
+        assert_eq!(connection("wss://db.example.com").http_endpoint(), "https://db.example.com");
+        assert_eq!(connection("https://db.example.com").endpoint(), "https://db.example.com");
+    }
+
+    #[test]
+    fn test_read_only() {
+        assert!(DbAuth::Record { access: "rag_user".to_string() }.is_read_only());
+        assert!(!DbAuth::Database.is_read_only());
+        assert!(!DbAuth::Token("jwt".to_string()).is_read_only());
+    }
+}
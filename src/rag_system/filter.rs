@@ -0,0 +1,261 @@
+use anyhow::{ bail, Result };
+use serde::{ Deserialize, Serialize };
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+// Typed filter expression over document `metadata` fields and `created_at`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(clippy::large_enum_variant)]
+pub enum Filter {
+    Eq {
+        field: String,
+        value: Value,
+    },
+    In {
+        field: String,
+        values: Vec<Value>,
+    },
+    Range {
+        field: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gt: Option<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gte: Option<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lt: Option<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        lte: Option<Value>,
+    },
+    Contains {
+        field: String,
+        value: Value,
+    },
+    Exists {
+        field: String,
+    },
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+}
+
+/// Compiled filter, a SurrealQL condition and the parameters it references
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompiledFilter {
+    pub condition: String,
+    pub params: BTreeMap<String, Value>,
+}
+
+impl Filter {
+    /// Combines filters with AND, returning `None` when there is nothing to filter on
+    pub fn all(filters: Vec<Filter>) -> Option<Filter> {
+        match filters.len() {
+            0 => None,
+            1 => filters.into_iter().next(),
+            _ => Some(Filter::And(filters)),
+        }
+    }
+
+    /// Reads a filter from JSON: an expression string, a list of expressions and filters combined with AND, or a filter tree
+    pub fn from_value(value: Option<Value>) -> Result<Option<Filter>> {
+        match value {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(expression)) => expression.parse().map(Some),
+            Some(Value::Array(filters)) => {
+                let filters = filters
+                    .into_iter()
+                    .map(|filter| match filter {
+                        Value::String(expression) => expression.parse(),
+                        filter => Ok(serde_json::from_value(filter)?),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(Filter::all(filters))
+            }
+            Some(filter) => Ok(Some(serde_json::from_value(filter)?)),
+        }
+    }
+
+    /// Compiles the expression into a WHERE condition with bound parameters
+    pub fn compile(&self) -> Result<CompiledFilter> {
+        let mut params = BTreeMap::new();
+        let condition = self.compile_into(&mut params)?;
+        Ok(CompiledFilter { condition, params })
+    }
+
+    fn compile_into(&self, params: &mut BTreeMap<String, Value>) -> Result<String> {
+        let condition = match self {
+            Filter::Eq { field, value } => format!("{} = {}", field_path(field)?, bind(params, value.clone())),
+            Filter::In { field, values } => format!("{} IN {}", field_path(field)?, bind(params, Value::Array(values.clone()))),
+            Filter::Range { field, gt, gte, lt, lte } => {
+                let path = field_path(field)?;
+                let mut bounds = Vec::new();
+                for (operator, bound) in [(">", gt), (">=", gte), ("<", lt), ("<=", lte)] {
+                    if let Some(value) = bound {
+                        bounds.push(format!("{} {} {}", path, operator, bind(params, value.clone())));
+                    }
+                }
+                if bounds.is_empty() {
+                    bail!("Range filter on '{}' requires at least one bound", field);
+                }
+                bounds.join(" AND ")
+            }
+            Filter::Contains { field, value } => format!("{} CONTAINS {}", field_path(field)?, bind(params, value.clone())),
+            // Tags, groups and attributes default to empty, they exist when not empty. Chunks stored before they
+            // were typed may lack them, the functions fail on NONE
+            Filter::Exists { field } => {
+                let path = field_path(field)?;
+                match path.as_str() {
+                    "metadata.tags" | "metadata.groups" => format!("array::len({} ?? []) > 0", path),
+                    "metadata.attributes" => format!("object::len({} ?? {{}}) > 0", path),
+                    _ => format!("{} != NONE", path),
+                }
+            }
+            Filter::And(filters) | Filter::Or(filters) => {
+                if filters.is_empty() {
+                    bail!("Empty and/or filter");
+                }
+                let operator = if matches!(self, Filter::And(_)) { " AND " } else { " OR " };
+                filters
+                    .iter()
+                    .map(|filter| filter.compile_into(params).map(|condition| format!("({})", condition)))
+                    .collect::<Result<Vec<_>>>()?
+                    .join(operator)
+            }
+        };
+
+        Ok(condition)
+    }
+}
+
+/// Registers a parameter and returns its placeholder
+fn bind(params: &mut BTreeMap<String, Value>, value: Value) -> String {
+    let name = format!("filter_{}", params.len());
+    params.insert(name.clone(), value);
+    format!("${}", name)
+}
+
+/// Checks a single field path segment, only plain identifiers are accepted
+pub(crate) fn is_identifier(segment: &str) -> bool {
+    let mut chars = segment.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_') && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Maps a user facing field name to its document path, `source` becomes `metadata.source`
+fn field_path(field: &str) -> Result<String> {
+    let field = field.strip_prefix("metadata.").unwrap_or(field);
+    if !field.split('.').all(is_identifier) {
+        bail!("Invalid filter field '{}'", field);
+    }
+
+    Ok(if field == "created_at" { field.to_string() } else { format!("metadata.{}", field) })
+}
+
+/// Parses a literal value, falling back to a plain string
+fn parse_value(value: &str) -> Value {
+    serde_json::from_str::<Value>(value)
+        .ok()
+        .filter(|value| !value.is_object() && !value.is_array())
+        .unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+// Parses the CLI filter syntax: `field=value`, `field=a|b`, `field>=value`, `field<value`,
+// `field~value` (contains), `field?` (exists), or a JSON filter expression
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(expression: &str) -> Result<Self> {
+        let expression = expression.trim();
+        if expression.starts_with('{') {
+            return Ok(serde_json::from_str(expression)?);
+        }
+
+        // A trailing `?` after a value belongs to it, e.g. `title=What?`
+        if let Some(field) = expression.strip_suffix('?').filter(|field| !field.contains(['>', '<', '~', '='])) {
+            return Ok(Filter::Exists { field: field.trim().to_string() });
+        }
+
+        if let Some(position) = expression.find(['>', '<', '~', '=']) {
+            let rest = &expression[position..];
+            if let Some(operator) = [">=", "<=", ">", "<", "~", "="].into_iter().find(|operator| rest.starts_with(operator)) {
+                let field = expression[..position].trim().to_string();
+                let value = rest[operator.len()..].trim();
+                let bound = Some(parse_value(value));
+                return Ok(match operator {
+                    ">=" => Filter::Range { field, gt: None, gte: bound, lt: None, lte: None },
+                    "<=" => Filter::Range { field, gt: None, gte: None, lt: None, lte: bound },
+                    ">" => Filter::Range { field, gt: bound, gte: None, lt: None, lte: None },
+                    "<" => Filter::Range { field, gt: None, gte: None, lt: bound, lte: None },
+                    "~" => Filter::Contains { field, value: parse_value(value) },
+                    _ if value.contains('|') => Filter::In { field, values: value.split('|').map(parse_value).collect() },
+                    _ => Filter::Eq { field, value: parse_value(value) },
+                });
+            }
+        }
+
+        bail!("Invalid filter expression '{}'", expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_filter() {
+        assert_eq!("source=The_Last_Question.pdf".parse::<Filter>().unwrap(), Filter::Eq {
+            field: "source".to_string(),
+            value: json!("The_Last_Question.pdf"),
+        });
+        assert_eq!("index=a|2".parse::<Filter>().unwrap(), Filter::In { field: "index".to_string(), values: vec![json!("a"), json!(2)] });
+        assert_eq!("created_at>=2025-07-01".parse::<Filter>().unwrap(), Filter::Range {
+            field: "created_at".to_string(),
+            gt: None,
+            gte: Some(json!("2025-07-01")),
+            lt: None,
+            lte: None,
+        });
+        assert_eq!("tags?".parse::<Filter>().unwrap(), Filter::Exists { field: "tags".to_string() });
+        assert_eq!("title=What?".parse::<Filter>().unwrap(), Filter::Eq { field: "title".to_string(), value: json!("What?") });
+        assert_eq!("source~faq?".parse::<Filter>().unwrap(), Filter::Contains { field: "source".to_string(), value: json!("faq?") });
+        assert!("nonsense".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn test_filter_from_value() {
+        assert_eq!(Filter::from_value(None).unwrap(), None);
+        assert_eq!(
+            Filter::from_value(Some(json!("source=a.pdf"))).unwrap(),
+            Some(Filter::Eq { field: "source".to_string(), value: json!("a.pdf") })
+        );
+        assert!(matches!(Filter::from_value(Some(json!(["source=a.pdf", "tags?"]))).unwrap(), Some(Filter::And(filters)) if filters.len() == 2));
+        assert_eq!(Filter::from_value(Some(json!({ "exists": { "field": "tags" } }))).unwrap(), Some(Filter::Exists { field: "tags".to_string() }));
+        assert!(Filter::from_value(Some(json!("nonsense"))).is_err());
+    }
+
+    #[test]
+    fn test_compile_filter() {
+        let filter = Filter::And(vec![
+            Filter::Eq { field: "source".to_string(), value: json!("The_Last_Question.pdf") },
+            Filter::Or(vec![Filter::Exists { field: "tags".to_string() }, Filter::Range {
+                field: "created_at".to_string(),
+                gt: None,
+                gte: Some(json!("2025-07-01")),
+                lt: Some(json!("2025-08-01")),
+                lte: None,
+            }])
+        ]);
+        let compiled = filter.compile().unwrap();
+        assert_eq!(
+            compiled.condition,
+            "(metadata.source = $filter_0) AND ((array::len(metadata.tags ?? []) > 0) OR (created_at >= $filter_1 AND created_at < $filter_2))"
+        );
+        assert_eq!(compiled.params.len(), 3);
+    }
+
+    #[test]
+    fn test_reject_invalid_field() {
+        let filter = Filter::Exists { field: "source; DELETE documents".to_string() };
+        assert!(filter.compile().is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use anyhow::Result;
+use serde::{ Deserialize, Serialize };
+use surrealdb::engine::any::Any;
+use surrealdb::Surreal;
+use tracing::info;
+
+use super::structures::Collection;
+
+// Versioned schema change, applied in order and recorded in the `_migrations` table
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    /// SurrealQL run once per database
+    pub database: &'static str,
+    /// SurrealQL run once per collection table, `{table}` and `{dimension}` are substituted
+    pub collection: &'static str,
+}
+
+// Schema history, append new migrations at the end and never edit applied ones
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "documents table with vector index",
+        database: "",
+        collection: "
+            DEFINE TABLE IF NOT EXISTS {table} SCHEMAFULL;
+            DEFINE FIELD IF NOT EXISTS id ON {table} TYPE string;
+            DEFINE FIELD IF NOT EXISTS content ON {table} TYPE string;
+            DEFINE FIELD IF NOT EXISTS embedding ON {table} TYPE array<float>;
+            DEFINE FIELD IF NOT EXISTS metadata ON {table} TYPE object;
+            DEFINE FIELD IF NOT EXISTS created_at ON {table} TYPE string;
+            DEFINE INDEX IF NOT EXISTS embedding_idx ON {table} FIELDS embedding MTREE DIMENSION {dimension};
+        ",
+    },
+    Migration {
+        version: 2,
+        name: "typed metadata with user tags and attributes",
+        database: "",
+        collection: "
+            DEFINE FIELD IF NOT EXISTS metadata.index ON {table} TYPE int;
+            DEFINE FIELD IF NOT EXISTS metadata.source ON {table} TYPE string;
+            DEFINE FIELD IF NOT EXISTS metadata.tags ON {table} TYPE array<string> DEFAULT [];
+            DEFINE FIELD IF NOT EXISTS metadata.attributes ON {table} FLEXIBLE TYPE object DEFAULT {};
+            UPDATE {table} SET
+                    metadata.index = metadata.index ?? 0,
+                    metadata.source = metadata.source ?? '',
+                    metadata.tags = metadata.tags ?? [],
+                    metadata.attributes = metadata.attributes ?? {}
+                WHERE metadata.index = NONE OR metadata.source = NONE OR metadata.tags = NONE OR metadata.attributes = NONE;
+        ",
+    },
+    Migration {
+        version: 3,
+        name: "collections registry",
+        database: "
+            DEFINE TABLE IF NOT EXISTS collections SCHEMAFULL;
+            DEFINE FIELD IF NOT EXISTS name ON collections TYPE string;
+            DEFINE FIELD IF NOT EXISTS `table` ON collections TYPE string;
+            DEFINE FIELD IF NOT EXISTS embedding_model ON collections TYPE string;
+            DEFINE FIELD IF NOT EXISTS dimension ON collections TYPE int;
+            DEFINE FIELD IF NOT EXISTS created_at ON collections TYPE string;
+        ",
+        collection: "",
+    },
+    Migration {
+        version: 4,
+        name: "hashed API keys with scopes and daily quotas",
+        database: "
+            DEFINE TABLE IF NOT EXISTS _api_keys SCHEMAFULL;
+            DEFINE FIELD IF NOT EXISTS name ON _api_keys TYPE string;
+            DEFINE FIELD IF NOT EXISTS key_hash ON _api_keys TYPE string;
+            DEFINE FIELD IF NOT EXISTS prefix ON _api_keys TYPE string;
+            DEFINE FIELD IF NOT EXISTS scopes ON _api_keys TYPE array<string>;
+            DEFINE FIELD IF NOT EXISTS requests_per_day ON _api_keys TYPE option<int>;
+            DEFINE FIELD IF NOT EXISTS tokens_per_day ON _api_keys TYPE option<int>;
+            DEFINE FIELD IF NOT EXISTS usage_day ON _api_keys TYPE option<string>;
+            DEFINE FIELD IF NOT EXISTS requests ON _api_keys TYPE int DEFAULT 0;
+            DEFINE FIELD IF NOT EXISTS tokens ON _api_keys TYPE int DEFAULT 0;
+            DEFINE FIELD IF NOT EXISTS created_at ON _api_keys TYPE string;
+            DEFINE FIELD IF NOT EXISTS revoked_at ON _api_keys TYPE option<string>;
+            DEFINE INDEX IF NOT EXISTS key_hash_idx ON _api_keys FIELDS key_hash UNIQUE;
+        ",
+        collection: "",
+    },
+    Migration {
+        version: 5,
+        name: "document access control with record user permissions",
+        database: "
+            DEFINE FIELD IF NOT EXISTS groups ON _api_keys TYPE array<string> DEFAULT [];
+            DEFINE TABLE IF NOT EXISTS rag_users SCHEMAFULL
+                PERMISSIONS FOR select WHERE id = $auth.id FOR create, update, delete NONE;
+            DEFINE FIELD IF NOT EXISTS name ON rag_users TYPE string;
+            DEFINE FIELD IF NOT EXISTS password ON rag_users TYPE string;
+            DEFINE FIELD IF NOT EXISTS groups ON rag_users TYPE array<string> DEFAULT [];
+            DEFINE INDEX IF NOT EXISTS name_idx ON rag_users FIELDS name UNIQUE;
+            DEFINE ACCESS IF NOT EXISTS rag_user ON DATABASE TYPE RECORD
+                SIGNIN (SELECT * FROM rag_users WHERE name = $name AND crypto::argon2::compare(password, $password))
+                DURATION FOR SESSION 12h;
+        ",
+        collection: "
+            DEFINE FIELD IF NOT EXISTS metadata.owner ON {table} TYPE option<string>;
+            DEFINE FIELD IF NOT EXISTS metadata.groups ON {table} TYPE array<string> DEFAULT [];
+            UPDATE {table} SET metadata.groups = [] WHERE metadata.groups = NONE;
+            ALTER TABLE {table} PERMISSIONS
+                FOR select WHERE (metadata.owner = NONE AND metadata.groups = []) OR metadata.owner = $auth.name OR metadata.groups CONTAINSANY $auth.groups
+                FOR create, update, delete NONE;
+        ",
+    },
+    Migration {
+        version: 6,
+        name: "tenant registry and tenant bound API keys",
+        database: "
+            DEFINE TABLE IF NOT EXISTS _tenants SCHEMAFULL;
+            DEFINE FIELD IF NOT EXISTS name ON _tenants TYPE string;
+            DEFINE FIELD IF NOT EXISTS namespace ON _tenants TYPE string;
+            DEFINE FIELD IF NOT EXISTS database ON _tenants TYPE string;
+            DEFINE FIELD IF NOT EXISTS created_at ON _tenants TYPE string;
+            DEFINE FIELD IF NOT EXISTS tenant ON _api_keys TYPE option<string>;
+        ",
+        collection: "",
+    },
+    Migration {
+        version: 7,
+        name: "read-only collection and migration registries for record users",
+        database: "
+            ALTER TABLE collections PERMISSIONS FOR select FULL FOR create, update, delete NONE;
+            ALTER TABLE _migrations PERMISSIONS FOR select FULL FOR create, update, delete NONE;
+        ",
+        collection: "",
+    },
+    Migration {
+        version: 8,
+        name: "re-embedding marker on collections",
+        database: "
+            DEFINE FIELD IF NOT EXISTS reembedding ON collections TYPE option<string>;
+        ",
+        collection: "",
+    }
+];
+
+// Applied migration record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    pub applied_at: String,
+}
+
+// Migration with its applied state
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: u32,
+    pub name: &'static str,
+    pub applied_at: Option<String>,
+}
+
+/// Latest schema version known to this build
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|migration| migration.version).unwrap_or_default()
+}
+
+/// Collection part of a migration for one table
+fn render(sql: &str, table: &str, dimension: u16) -> String {
+    sql.replace("{table}", table).replace("{dimension}", &dimension.to_string())
+}
+
+/// Collection schema at the latest version, used for new collection tables
+pub fn collection_schema(table: &str, dimension: u16) -> String {
+    MIGRATIONS.iter()
+        .map(|migration| render(migration.collection, table, dimension))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn bootstrap(db: &Surreal<Any>) -> Result<()> {
+    db.query(
+        "
+        DEFINE TABLE IF NOT EXISTS _migrations SCHEMAFULL;
+        DEFINE FIELD IF NOT EXISTS version ON _migrations TYPE int;
+        DEFINE FIELD IF NOT EXISTS name ON _migrations TYPE string;
+        DEFINE FIELD IF NOT EXISTS applied_at ON _migrations TYPE string;
+        "
+    ).await?
+        .check()?;
+
+    Ok(())
+}
+
+pub async fn applied(db: &Surreal<Any>) -> Result<Vec<AppliedMigration>> {
+    bootstrap(db).await?;
+    recorded(db).await
+}
+
+/// Reads the applied migrations without defining `_migrations`, so read-only credentials can inspect them
+async fn recorded(db: &Surreal<Any>) -> Result<Vec<AppliedMigration>> {
+    match db.query("SELECT * OMIT id FROM _migrations ORDER BY version").await?.take(0) {
+        Ok(applied) => Ok(applied),
+        // A database that never ran a migration has no `_migrations` table
+        Err(e) if e.to_string().contains("does not exist") => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub async fn status(db: &Surreal<Any>) -> Result<Vec<MigrationStatus>> {
+    let applied = recorded(db).await?;
+
+    Ok(
+        MIGRATIONS.iter()
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                name: migration.name,
+                applied_at: applied
+                    .iter()
+                    .find(|applied| applied.version == migration.version)
+                    .map(|applied| applied.applied_at.clone()),
+            })
+            .collect()
+    )
+}
+
+/// Applies pending migrations in order, each in its own transaction, returning the applied versions
+pub async fn up(db: &Surreal<Any>, collections: &[Collection]) -> Result<Vec<u32>> {
+    let applied = applied(db).await?;
+    let mut versions = Vec::new();
+
+    for migration in MIGRATIONS.iter().filter(|migration| !applied.iter().any(|applied| applied.version == migration.version)) {
+        let mut statements = vec![migration.database.to_string()];
+        for collection in collections {
+            statements.push(render(migration.collection, &collection.table, collection.dimension));
+        }
+
+        db.query(format!("BEGIN TRANSACTION;\n{}\nCREATE type::thing('_migrations', $version) CONTENT $record;\nCOMMIT TRANSACTION;", statements.join("\n")))
+            .bind(("version", migration.version))
+            .bind(("record", AppliedMigration {
+                version: migration.version,
+                name: migration.name.to_string(),
+                applied_at: chrono::Utc::now().to_rfc3339(),
+            })).await?
+            .check()?;
+
+        info!("Applied migration {}: {}", migration.version, migration.name);
+        versions.push(migration.version);
+    }
+
+    Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, (i as u32) + 1);
+        }
+    }
+
+    #[test]
+    fn test_collection_schema() {
+        let schema = collection_schema("documents_hr", 1024);
+        assert!(schema.contains("DEFINE TABLE IF NOT EXISTS documents_hr SCHEMAFULL;"));
+        assert!(schema.contains("MTREE DIMENSION 1024;"));
+        assert!(schema.contains("FLEXIBLE TYPE object DEFAULT {};"));
+        assert!(schema.contains("ALTER TABLE documents_hr PERMISSIONS"));
+        assert!(!schema.contains("{table}"));
+    }
+
+    #[tokio::test]
+    async fn test_status_leaves_schema_untouched() {
+        let db = surrealdb::engine::any::connect("mem://").await.unwrap();
+        db.use_ns("rag").use_db("documents").await.unwrap();
+
+        let pending = status(&db).await.unwrap();
+        assert_eq!(pending.len(), MIGRATIONS.len());
+        assert!(pending.iter().all(|migration| migration.applied_at.is_none()));
+        let info: Option<serde_json::Value> = db.query("INFO FOR DB").await.unwrap().take(0).unwrap();
+        assert!(info.unwrap()["tables"].get("_migrations").is_none());
+
+        up(&db, &[]).await.unwrap();
+        assert!(status(&db).await.unwrap().iter().all(|migration| migration.applied_at.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_backfill_tags_and_attributes() {
+        let db = surrealdb::engine::any::connect("mem://").await.unwrap();
+        db.use_ns("rag").use_db("documents").await.unwrap();
+        // Chunk stored before migration 2 typed its metadata, the untyped object kept none of it
+        db.query(render(MIGRATIONS[0].collection, "documents", 3)).await.unwrap().check().unwrap();
+        db.query("CREATE documents:old SET content = 'old', embedding = [1.0, 1.0, 1.0], metadata = { index: 0, source: 'a.pdf' }, created_at = ''")
+            .await.unwrap()
+            .check().unwrap();
+
+        let collection = Collection {
+            name: "documents".to_string(),
+            table: "documents".to_string(),
+            embedding_model: "nomic-embed-text".to_string(),
+            dimension: 3,
+            created_at: String::new(),
+            reembedding: None,
+        };
+        up(&db, &[collection]).await.unwrap();
+
+        let metadata: Option<serde_json::Value> = db.query("SELECT VALUE metadata FROM ONLY documents:old").await.unwrap().take(0).unwrap();
+        let metadata = metadata.unwrap();
+        assert_eq!((&metadata["tags"], &metadata["attributes"], &metadata["groups"]), (&serde_json::json!([]), &serde_json::json!({}), &serde_json::json!([])));
+    }
+}
@@ -1,5 +1,52 @@
+mod acl;
+mod api_keys;
+mod backend;
+mod connection;
+mod filter;
+mod migrations;
+pub(crate) mod ollama;
+mod openai;
+mod pool;
+#[allow(clippy::module_inception)]
 mod rag_system;
+mod reranker;
+mod resilience;
 mod structures;
 
+pub use acl::Identity;
+pub use api_keys::{ ApiKey, ApiScope };
+pub use backend::{ Api, ModelBackend };
+pub use connection::{ DbAuth, DbConnection };
+pub use filter::{ CompiledFilter, Filter };
+pub use ollama::OllamaClient;
+pub use openai::OpenAiClient;
+pub use pool::Balance;
+pub use migrations::{ latest_version, Migration, MigrationStatus, MIGRATIONS };
 pub use rag_system::RagSystem;
-pub use structures::DocumentMetaData;
+pub use reranker::Reranker;
+pub use structures::{
+    BackupManifest,
+    Collection,
+    DocumentMetaData,
+    DocumentOrder,
+    DocumentTarget,
+    ExportChunk,
+    ExportHeader,
+    ExportRecord,
+    Generation,
+    GenerationChunk,
+    KnowledgeBaseStats,
+    ListOptions,
+    MetadataUpdate,
+    ModelInfo,
+    OllamaModel,
+    OllamaPullProgress,
+    QueryOptions,
+    RagAnswer,
+    Scope,
+    SourceReference,
+    SourceStats,
+    SourceSummary,
+    Tenant,
+    UserMetaData,
+};
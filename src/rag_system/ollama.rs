@@ -0,0 +1,447 @@
+use anyhow::{ anyhow, bail, Result };
+use futures::stream::{ self, BoxStream, StreamExt };
+use reqwest::{ Client, Response };
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::Configuration;
+use super::pool::{ take_line, Balance, ServerPool };
+use super::resilience::RetryPolicy;
+use super::structures::{
+    GenerationChunk,
+    ModelInfo,
+    OllamaEmbedResponse,
+    OllamaEmbeddingResponse,
+    OllamaGenerationResponse,
+    OllamaModel,
+    OllamaPullProgress,
+    OllamaShowResponse,
+    OllamaTagsResponse,
+    OllamaVersionResponse,
+};
+
+/// Stream of the JSON objects of a newline delimited response body
+fn json_lines<T: DeserializeOwned + Send + 'static>(response: Response) -> BoxStream<'static, Result<T>> {
+    stream::try_unfold((response, Vec::new()), |(mut response, mut buffer)| async move {
+        loop {
+            if let Some(line) = take_line(&mut buffer) {
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                let item: T = serde_json::from_slice(&line)?;
+                return Ok(Some((item, (response, buffer))));
+            }
+            match response.chunk().await? {
+                Some(bytes) => buffer.extend_from_slice(&bytes),
+                None if buffer.iter().all(u8::is_ascii_whitespace) => {
+                    return Ok::<_, anyhow::Error>(None);
+                }
+                // Last line without a trailing newline
+                None => buffer.push(b'\n'),
+            }
+        }
+    }).boxed()
+}
+
+/// Whether a model is available, a name without tag matches `:latest`
+pub fn has_model(models: &[OllamaModel], name: &str) -> bool {
+    let name = if name.contains(':') { name.to_string() } else { format!("{}:latest", name) };
+    models.iter().any(|model| model.name == name)
+}
+
+/// Weighted endpoints of `ollama_urls` (`url` or `url=weight`, comma separated), `ollama_url` when it is empty
+pub fn endpoints_from_config(cfg: &Configuration) -> Result<Vec<(String, u32)>> {
+    if cfg.ollama_urls.trim().is_empty() {
+        return Ok(vec![(cfg.ollama_url.clone(), 1)]);
+    }
+
+    let mut endpoints = Vec::new();
+    for entry in cfg.ollama_urls.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (url, weight) = match entry.rsplit_once('=') {
+            Some((url, weight)) => {
+                let weight = weight
+                    .trim()
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|weight| *weight > 0)
+                    .ok_or_else(|| anyhow!("Ollama endpoint '{}' has an invalid weight '{}', expected a positive number", url, weight))?;
+                (url.trim(), weight)
+            }
+            None => (entry, 1),
+        };
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            bail!("Ollama endpoint '{}' must start with http:// or https://", url);
+        }
+        endpoints.push((url.to_string(), weight));
+    }
+    if endpoints.is_empty() {
+        bail!("ollama_urls '{}' lists no endpoint", cfg.ollama_urls);
+    }
+
+    Ok(endpoints)
+}
+
+/// Retry policy of the `ollama_*` settings, shared by the OpenAI-compatible backend
+pub(super) fn retry_from_config(cfg: &Configuration) -> RetryPolicy {
+    RetryPolicy {
+        max_retries: cfg.ollama_max_retries,
+        base_delay: Duration::from_millis(cfg.ollama_retry_base_delay_ms),
+        max_delay: Duration::from_millis(cfg.ollama_retry_max_delay_ms),
+    }
+}
+
+// Ollama REST API client over one or more servers, with timeouts, retries, failover and a circuit breaker per server
+#[derive(Clone)]
+pub struct OllamaClient {
+    pool: ServerPool,
+}
+
+impl OllamaClient {
+    pub fn new(
+        endpoints: &[(String, u32)],
+        balance: Balance,
+        connect_timeout: Duration,
+        request_timeout: Duration,
+        retry: RetryPolicy,
+        breaker_threshold: u32,
+        breaker_cooldown: Duration
+    ) -> Result<Self> {
+        Ok(OllamaClient {
+            pool: ServerPool::new("Ollama", endpoints, balance, connect_timeout, request_timeout, retry, breaker_threshold, breaker_cooldown)?,
+        })
+    }
+
+    pub fn from_config(cfg: &Configuration) -> Result<Self> {
+        Self::new(
+            &endpoints_from_config(cfg)?,
+            Balance::from_config(cfg)?,
+            Duration::from_secs(cfg.ollama_connect_timeout_secs),
+            Duration::from_secs(cfg.ollama_request_timeout_secs),
+            retry_from_config(cfg),
+            cfg.ollama_breaker_threshold,
+            Duration::from_secs(cfg.ollama_breaker_cooldown_secs)
+        )
+    }
+
+    /// Whether both clients send to the same servers, sharing their breakers
+    pub fn shares_servers(&self, other: &OllamaClient) -> bool {
+        self.pool.shares_servers(&other.pool)
+    }
+
+    /// Base URL of the server, comma separated URLs of a pool
+    pub fn url(&self) -> String {
+        self.pool.url()
+    }
+
+    /// A client per server, sharing its breaker and load, for per-server operations like pulls
+    pub fn endpoints(&self) -> Vec<OllamaClient> {
+        self.pool
+            .split()
+            .into_iter()
+            .map(|pool| OllamaClient { pool })
+            .collect()
+    }
+
+    /// Requests worth sending in parallel to keep every server busy, the sum of the weights
+    pub fn concurrency(&self) -> usize {
+        self.pool.concurrency()
+    }
+
+    /// Underlying HTTP client, for calls to other local services
+    pub fn http(&self) -> &Client {
+        self.pool.http()
+    }
+
+    /// Probes every endpoint once, opening the breaker of the unreachable ones and closing it for the others
+    pub async fn check_health(&self) -> Vec<(String, Result<String>)> {
+        self.pool.check_health("/api/version", |response| async move { Ok(response.json::<OllamaVersionResponse>().await?.version) }).await
+    }
+
+    /// Version of the Ollama server
+    pub async fn version(&self) -> Result<String> {
+        let response = self.pool.send(false, |http, url| http.get(format!("{}/api/version", url))).await?;
+        let version: OllamaVersionResponse = response.json().await?;
+
+        Ok(version.version)
+    }
+
+    /// Models available on the Ollama server
+    pub async fn tags(&self) -> Result<Vec<OllamaModel>> {
+        let response = self.pool.send(false, |http, url| http.get(format!("{}/api/tags", url))).await?;
+        let tags: OllamaTagsResponse = response.json().await?;
+
+        Ok(tags.models)
+    }
+
+    /// Details of a local model, including its context length
+    pub async fn show(&self, model: &str) -> Result<ModelInfo> {
+        let response = self.pool.send(false, |http, url| http.post(format!("{}/api/show", url)).json(&json!({ "model": model }))).await?;
+        let show: OllamaShowResponse = response.json().await?;
+
+        Ok(ModelInfo::new(model, show))
+    }
+
+    /// Pulls a model, streaming the progress lines sent by Ollama
+    pub async fn pull(&self, model: &str) -> Result<BoxStream<'static, Result<OllamaPullProgress>>> {
+        let response = self.pool.send(true, |http, url| {
+            http.post(format!("{}/api/pull", url)).json(&json!({
+                "model": model,
+                "stream": true
+            }))
+        }).await?;
+
+        Ok(
+            json_lines::<OllamaPullProgress>(response)
+                .map(|progress| {
+                    let progress = progress?;
+                    match &progress.error {
+                        Some(error) => Err(anyhow!("Pull failed: {}", error)),
+                        None => Ok(progress),
+                    }
+                })
+                .boxed()
+        )
+    }
+
+    /// Embedding of a single text
+    pub async fn embedding(&self, model: &str, text: &str) -> Result<Vec<f32>> {
+        let response = self.pool.send(false, |http, url| {
+            http.post(format!("{}/api/embeddings", url)).json(&json!({
+                "model": model,
+                "prompt": text
+            }))
+        }).await?;
+
+        let embedding: OllamaEmbeddingResponse = response.json().await?;
+        Ok(embedding.embedding)
+    }
+
+    /// Embeddings of a batch of texts
+    pub async fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let response = self.pool.send(false, |http, url| {
+            http.post(format!("{}/api/embed", url)).json(&json!({
+                "model": model,
+                "input": texts
+            }))
+        }).await?;
+
+        let embed: OllamaEmbedResponse = response.json().await?;
+        Ok(embed.embeddings)
+    }
+
+    /// Non streaming completion of a raw prompt
+    pub async fn generate(&self, model: &str, prompt: &str) -> Result<OllamaGenerationResponse> {
+        let response = self.pool.send(false, |http, url| {
+            http.post(format!("{}/api/generate", url)).json(&json!({
+                "model": model,
+                "prompt": prompt,
+                "stream": false
+            }))
+        }).await?;
+
+        let generation: OllamaGenerationResponse = response.json().await?;
+        Ok(generation)
+    }
+
+    /// Streamed completion of a raw prompt, Ollama sends one JSON object per line
+    pub async fn generate_stream(&self, model: &str, prompt: &str) -> Result<BoxStream<'static, Result<GenerationChunk>>> {
+        let response = self.pool.send(true, |http, url| {
+            http.post(format!("{}/api/generate", url)).json(&json!({
+                "model": model,
+                "prompt": prompt,
+                "stream": true
+            }))
+        }).await?;
+
+        Ok(
+            json_lines::<OllamaGenerationResponse>(response)
+                .map(|generation| generation.map(GenerationChunk::from))
+                .boxed()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use axum::routing::{ get, post };
+    use axum::{ Json, Router };
+    use std::sync::atomic::{ AtomicUsize, Ordering };
+    use std::sync::Arc;
+
+    /// Serves `router` on a free local port, returning its base URL
+    async fn stub(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn pool(endpoints: &[(String, u32)], balance: Balance, max_retries: u32, breaker_threshold: u32) -> OllamaClient {
+        OllamaClient::new(
+            endpoints,
+            balance,
+            Duration::from_secs(1),
+            Duration::from_secs(5),
+            RetryPolicy { max_retries, base_delay: Duration::from_millis(1), max_delay: Duration::from_millis(5) },
+            breaker_threshold,
+            Duration::from_secs(60)
+        ).unwrap()
+    }
+
+    fn client(url: &str, max_retries: u32, breaker_threshold: u32) -> OllamaClient {
+        pool(&[(url.to_string(), 1)], Balance::RoundRobin, max_retries, breaker_threshold)
+    }
+
+    /// Stub answering `/api/version` with `status`, counting the calls
+    async fn version_stub(status: StatusCode) -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let url = stub(
+            Router::new().route(
+                "/api/version",
+                get(move || async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    (status, Json(json!({ "version": "0.9.0", "error": "out of memory" })))
+                })
+            )
+        ).await;
+        (url, calls)
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let url = stub(
+            Router::new().route(
+                "/api/version",
+                get(move || async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                        (StatusCode::SERVICE_UNAVAILABLE, Json(json!({ "error": "loading model" })))
+                    } else {
+                        (StatusCode::OK, Json(json!({ "version": "0.9.0" })))
+                    }
+                })
+            )
+        ).await;
+
+        assert_eq!(client(&url, 3, 5).version().await.unwrap(), "0.9.0");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_error_body_and_circuit_breaker() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let url = stub(
+            Router::new()
+                .route(
+                    "/api/generate",
+                    post(|| async { (StatusCode::NOT_FOUND, Json(json!({ "error": "model \"llama3.2\" not found, try pulling it first" }))) })
+                )
+                .route(
+                    "/api/tags",
+                    get(move || async move {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": "out of memory" })))
+                    })
+                )
+        ).await;
+        let ollama = client(&url, 1, 2);
+
+        // Client errors are surfaced with the server's message and not retried
+        let error = format!("{:#}", ollama.generate("llama3.2", "hi").await.unwrap_err());
+        assert!(error.contains("404 Not Found: model \"llama3.2\" not found, try pulling it first"), "{}", error);
+
+        let error = format!("{:#}", ollama.tags().await.unwrap_err());
+        assert!(error.contains("failed after 2 attempt(s)") && error.contains("out of memory"), "{}", error);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // The breaker is open, the next call fails fast without reaching the server
+        let error = ollama.tags().await.unwrap_err().to_string();
+        assert!(error.contains("circuit breaker open"), "{}", error);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_weighted_round_robin() {
+        let (first, first_calls) = version_stub(StatusCode::OK).await;
+        let (second, second_calls) = version_stub(StatusCode::OK).await;
+        let ollama = pool(&[(first, 2), (second, 1)], Balance::RoundRobin, 0, 5);
+        assert_eq!(ollama.concurrency(), 3);
+
+        for _ in 0..6 {
+            ollama.version().await.unwrap();
+        }
+        assert_eq!(first_calls.load(Ordering::SeqCst), 4);
+        assert_eq!(second_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failover() {
+        let (failing, failing_calls) = version_stub(StatusCode::INTERNAL_SERVER_ERROR).await;
+        let (healthy, healthy_calls) = version_stub(StatusCode::OK).await;
+        let ollama = pool(&[(failing, 1), (healthy, 1)], Balance::LeastLoaded, 2, 1);
+
+        // The failing server is tried once, its breaker opens and the healthy one takes every request
+        for _ in 0..4 {
+            assert_eq!(ollama.version().await.unwrap(), "0.9.0");
+        }
+        assert!(failing_calls.load(Ordering::SeqCst) <= 1);
+        assert_eq!(healthy_calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_check_health() {
+        let (healthy, healthy_calls) = version_stub(StatusCode::OK).await;
+        // Nothing listens on the port of a dropped listener
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let down = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let ollama = pool(&[(down.clone(), 1), (healthy.clone(), 1)], Balance::RoundRobin, 0, 5);
+        let health = ollama.check_health().await;
+        assert_eq!(health[0].0, down);
+        assert!(health[0].1.is_err());
+        assert_eq!(health[1].1.as_ref().unwrap(), "0.9.0");
+
+        // Requests skip the server that failed its health check
+        for _ in 0..3 {
+            ollama.version().await.unwrap();
+        }
+        assert_eq!(healthy_calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_endpoints_from_config() {
+        let mut cfg = Configuration::default();
+        assert_eq!(endpoints_from_config(&cfg).unwrap(), vec![(cfg.ollama_url.clone(), 1)]);
+
+        cfg.ollama_urls = "http://192.168.90.104:11434=3, http://192.168.90.133:11434".to_string();
+        assert_eq!(endpoints_from_config(&cfg).unwrap(), vec![
+            ("http://192.168.90.104:11434".to_string(), 3),
+            ("http://192.168.90.133:11434".to_string(), 1),
+        ]);
+
+        cfg.ollama_urls = "http://192.168.90.104:11434=0".to_string();
+        assert!(endpoints_from_config(&cfg).unwrap_err().to_string().contains("invalid weight '0'"));
+        cfg.ollama_urls = "192.168.90.104:11434".to_string();
+        assert!(endpoints_from_config(&cfg).unwrap_err().to_string().contains("must start with http://"));
+    }
+
+    #[test]
+    fn test_has_model() {
+        let models = vec![OllamaModel { name: "llama3.2:latest".to_string(), size: 0, modified_at: String::new() }, OllamaModel {
+            name: "bge-m3:567m".to_string(),
+            size: 0,
+            modified_at: String::new(),
+        }];
+        assert!(has_model(&models, "llama3.2"));
+        assert!(has_model(&models, "bge-m3:567m"));
+        assert!(!has_model(&models, "bge-m3"));
+        assert!(!has_model(&models, "nomic-embed-text"));
+    }
+}
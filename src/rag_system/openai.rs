@@ -0,0 +1,279 @@
+use anyhow::{ anyhow, Result };
+use futures::stream::{ self, BoxStream, StreamExt };
+use reqwest::{ Client, Response };
+use serde_json::json;
+use std::time::Duration;
+
+use crate::Configuration;
+use super::ollama::retry_from_config;
+use super::pool::{ take_line, Balance, ServerPool };
+use super::resilience::RetryPolicy;
+use super::structures::{ Generation, GenerationChunk, OpenAiChatResponse, OpenAiEmbeddingResponse, OpenAiModelsResponse, OpenAiUsage };
+
+/// Final chunk of a streamed chat completion, with the usage reported by the server
+fn chat_done(usage: OpenAiUsage) -> GenerationChunk {
+    GenerationChunk { done: true, prompt_tokens: usage.prompt_tokens, completion_tokens: usage.completion_tokens, ..Default::default() }
+}
+
+/// Stream of the text pieces of an OpenAI-compatible chat completion sent as server-sent events
+fn chat_chunks(response: Response) -> BoxStream<'static, Result<GenerationChunk>> {
+    stream::try_unfold((response, Vec::new(), OpenAiUsage::default(), false), |(mut response, mut buffer, mut usage, finished)| async move {
+        if finished {
+            return Ok(None);
+        }
+        loop {
+            if let Some(line) = take_line(&mut buffer) {
+                let line = String::from_utf8_lossy(&line);
+                // Blank separators, comments and `event:` lines carry no data
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                    continue;
+                };
+                if data == "[DONE]" {
+                    return Ok(Some((chat_done(usage), (response, buffer, usage, true))));
+                }
+                let chunk: OpenAiChatResponse = serde_json::from_str(data)?;
+                if let Some(chunk_usage) = chunk.usage {
+                    usage = chunk_usage;
+                }
+                let text = chunk.choices
+                    .into_iter()
+                    .filter_map(|choice| choice.delta.and_then(|delta| delta.content))
+                    .collect::<String>();
+                if !text.is_empty() {
+                    return Ok(Some((GenerationChunk { text, ..Default::default() }, (response, buffer, usage, false))));
+                }
+                continue;
+            }
+            match response.chunk().await? {
+                Some(bytes) => buffer.extend_from_slice(&bytes),
+                // Some servers close the stream without `[DONE]`
+                None if buffer.iter().all(u8::is_ascii_whitespace) => {
+                    return Ok::<_, anyhow::Error>(Some((chat_done(usage), (response, buffer, usage, true))));
+                }
+                None => buffer.push(b'\n'),
+            }
+        }
+    }).boxed()
+}
+
+// Client of an OpenAI-compatible server (llama.cpp server, vLLM, LM Studio), its base URL includes `/v1`.
+// Shares the timeouts, retries and circuit breaker settings of the Ollama client
+#[derive(Clone)]
+pub struct OpenAiClient {
+    pool: ServerPool,
+}
+
+impl OpenAiClient {
+    pub fn new(
+        url: &str,
+        api_key: Option<String>,
+        connect_timeout: Duration,
+        request_timeout: Duration,
+        retry: RetryPolicy,
+        breaker_threshold: u32,
+        breaker_cooldown: Duration
+    ) -> Result<Self> {
+        let pool = ServerPool::new(
+            "OpenAI-compatible",
+            &[(url.to_string(), 1)],
+            Balance::RoundRobin,
+            connect_timeout,
+            request_timeout,
+            retry,
+            breaker_threshold,
+            breaker_cooldown
+        )?;
+
+        Ok(OpenAiClient { pool: pool.with_api_key(api_key) })
+    }
+
+    /// Client of the server at `url`, with the configured API key
+    pub fn from_config(url: &str, cfg: &Configuration) -> Result<Self> {
+        Self::new(
+            url,
+            (!cfg.openai_api_key.is_empty()).then(|| cfg.openai_api_key.expose().to_string()),
+            Duration::from_secs(cfg.ollama_connect_timeout_secs),
+            Duration::from_secs(cfg.ollama_request_timeout_secs),
+            retry_from_config(cfg),
+            cfg.ollama_breaker_threshold,
+            Duration::from_secs(cfg.ollama_breaker_cooldown_secs)
+        )
+    }
+
+    /// Base URL of the server
+    pub fn url(&self) -> String {
+        self.pool.url()
+    }
+
+    /// Underlying HTTP client, for calls to other local services
+    pub fn http(&self) -> &Client {
+        self.pool.http()
+    }
+
+    /// Probes the server once, opening its breaker when it is unreachable
+    pub async fn check_health(&self) -> Vec<(String, Result<String>)> {
+        self.pool.check_health("/models", |response| async move {
+            response.json::<OpenAiModelsResponse>().await?;
+            Ok("OpenAI-compatible API".to_string())
+        }).await
+    }
+
+    /// Names of the models served
+    pub async fn models(&self) -> Result<Vec<String>> {
+        let response = self.pool.send(false, |http, url| http.get(format!("{}/models", url))).await?;
+        let models: OpenAiModelsResponse = response.json().await?;
+
+        Ok(
+            models.data
+                .into_iter()
+                .map(|model| model.id)
+                .collect()
+        )
+    }
+
+    /// Embeddings of a batch of texts, in the order of the texts
+    pub async fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let response = self.pool.send(false, |http, url| {
+            http.post(format!("{}/embeddings", url)).json(&json!({
+                "model": model,
+                "input": texts
+            }))
+        }).await?;
+
+        let mut embeddings: OpenAiEmbeddingResponse = response.json().await?;
+        embeddings.data.sort_by_key(|embedding| embedding.index);
+        Ok(
+            embeddings.data
+                .into_iter()
+                .map(|embedding| embedding.embedding)
+                .collect()
+        )
+    }
+
+    /// Embedding of a single text
+    pub async fn embedding(&self, model: &str, text: &str) -> Result<Vec<f32>> {
+        self
+            .embed(model, &[text.to_string()]).await?
+            .pop()
+            .ok_or_else(|| anyhow!("No embedding returned by {}", self.url()))
+    }
+
+    /// Non streaming chat completion of a prompt sent as the user message
+    pub async fn chat(&self, model: &str, prompt: &str) -> Result<Generation> {
+        let response = self.pool.send(false, |http, url| {
+            http.post(format!("{}/chat/completions", url)).json(&json!({
+                "model": model,
+                "messages": [{ "role": "user", "content": prompt }],
+                "stream": false
+            }))
+        }).await?;
+
+        let completion: OpenAiChatResponse = response.json().await?;
+        let usage = completion.usage.unwrap_or_default();
+        Ok(Generation {
+            text: completion.choices
+                .into_iter()
+                .next()
+                .and_then(|choice| choice.message)
+                .and_then(|message| message.content)
+                .unwrap_or_default(),
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        })
+    }
+
+    /// Streamed chat completion of a prompt, sent as server-sent events
+    pub async fn chat_stream(&self, model: &str, prompt: &str) -> Result<BoxStream<'static, Result<GenerationChunk>>> {
+        let response = self.pool.send(true, |http, url| {
+            http.post(format!("{}/chat/completions", url)).json(&json!({
+                "model": model,
+                "messages": [{ "role": "user", "content": prompt }],
+                "stream": true,
+                "stream_options": { "include_usage": true }
+            }))
+        }).await?;
+
+        Ok(chat_chunks(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{ HeaderMap, StatusCode };
+    use axum::response::IntoResponse;
+    use axum::routing::post;
+    use axum::{ Json, Router };
+
+    /// Serves `router` on a free local port, returning its base URL
+    async fn stub(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn client(url: &str, api_key: Option<&str>) -> OpenAiClient {
+        let retry = RetryPolicy { max_retries: 0, base_delay: Duration::from_millis(1), max_delay: Duration::from_millis(5) };
+        OpenAiClient::new(url, api_key.map(str::to_string), Duration::from_secs(1), Duration::from_secs(5), retry, 5, Duration::from_secs(60)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_openai_api() {
+        let authorized = |headers: &HeaderMap| headers.get("authorization").and_then(|value| value.to_str().ok()) == Some("Bearer sk-local");
+        let url = stub(
+            Router::new()
+                .route(
+                    "/v1/embeddings",
+                    post(move |headers: HeaderMap, Json(request): Json<serde_json::Value>| async move {
+                        if !authorized(&headers) {
+                            return (StatusCode::UNAUTHORIZED, Json(json!({ "error": { "message": "Invalid API Key", "type": "authentication_error" } })));
+                        }
+                        let count = request["input"].as_array().unwrap().len();
+                        // Out of order, as some servers answer batches
+                        let data = (0..count).rev().map(|index| json!({ "index": index, "embedding": [index as f32, 0.5] })).collect::<Vec<_>>();
+                        (StatusCode::OK, Json(json!({ "object": "list", "data": data })))
+                    })
+                )
+                .route(
+                    "/v1/chat/completions",
+                    post(|Json(request): Json<serde_json::Value>| async move {
+                        if request["stream"] == json!(true) {
+                            let events = [
+                                json!({ "choices": [{ "delta": { "role": "assistant" } }] }),
+                                json!({ "choices": [{ "delta": { "content": "Multivac" } }] }),
+                                json!({ "choices": [{ "delta": { "content": " answers" }, "finish_reason": "stop" }] }),
+                                json!({ "choices": [], "usage": { "prompt_tokens": 12, "completion_tokens": 2 } }),
+                            ];
+                            let body = events.iter().map(|event| format!("data: {}\n\n", event)).collect::<String>() + "data: [DONE]\n\n";
+                            return ([("content-type", "text/event-stream")], body).into_response();
+                        }
+                        let content = format!("echo: {}", request["messages"][0]["content"].as_str().unwrap());
+                        Json(json!({
+                            "choices": [{ "index": 0, "message": { "role": "assistant", "content": content }, "finish_reason": "stop" }],
+                            "usage": { "prompt_tokens": 5, "completion_tokens": 3 }
+                        })).into_response()
+                    })
+                )
+        ).await;
+        let url = format!("{}/v1", url);
+
+        let error = format!("{:#}", client(&url, None).embedding("bge-m3", "hi").await.unwrap_err());
+        assert!(error.contains("401 Unauthorized: Invalid API Key"), "{}", error);
+
+        let openai = client(&url, Some("sk-local"));
+        assert_eq!(openai.embed("bge-m3", &["first".to_string(), "second".to_string()]).await.unwrap(), vec![vec![0.0, 0.5], vec![1.0, 0.5]]);
+        assert_eq!(openai.embedding("bge-m3", "hi").await.unwrap(), vec![0.0, 0.5]);
+
+        let generation = openai.chat("qwen3", "hi").await.unwrap();
+        assert_eq!(generation.text, "echo: hi");
+        assert_eq!((generation.prompt_tokens, generation.completion_tokens), (5, 3));
+
+        let chunks = openai.chat_stream("qwen3", "hi").await.unwrap().collect::<Vec<_>>().await;
+        let chunks = chunks.into_iter().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(chunks.iter().map(|chunk| chunk.text.as_str()).collect::<String>(), "Multivac answers");
+        let last = chunks.last().unwrap();
+        assert!(last.done);
+        assert_eq!((last.prompt_tokens, last.completion_tokens), (12, 2));
+    }
+}
@@ -0,0 +1,324 @@
+use anyhow::{ anyhow, bail, Result };
+use reqwest::header::RETRY_AFTER;
+use reqwest::{ Client, RequestBuilder, Response };
+use std::future::Future;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::Configuration;
+use super::resilience::{ is_retryable, CircuitBreaker, RetryPolicy, StatusError };
+use super::structures::OllamaErrorResponse;
+
+/// Removes and returns the next complete line of a buffered response body
+pub(super) fn take_line(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    let end = buffer.iter().position(|byte| *byte == b'\n')?;
+    Some(buffer.drain(..=end).collect())
+}
+
+/// Fails on non-2xx responses with the error reported by the server instead of a JSON decode error
+pub async fn check_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = response.headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs);
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<OllamaErrorResponse>(&body).map(|error| error.error.message()).unwrap_or(body);
+
+    Err(StatusError { status, message: message.trim().to_string(), retry_after }.into())
+}
+
+// Balancing of requests over the servers of a pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Balance {
+    /// Endpoints take turns, as often as their weight
+    RoundRobin,
+    /// Endpoint with the fewest requests in flight relative to its weight
+    LeastLoaded,
+}
+
+impl Balance {
+    pub fn from_config(cfg: &Configuration) -> Result<Self> {
+        match cfg.ollama_balance.as_str() {
+            "" | "round-robin" => Ok(Balance::RoundRobin),
+            "least-loaded" => Ok(Balance::LeastLoaded),
+            other => bail!("Unknown Ollama balance '{}', expected one of: round-robin, least-loaded", other),
+        }
+    }
+}
+
+// Server of a pool, with its own circuit breaker and load
+struct Endpoint {
+    url: String,
+    weight: u32,
+    breaker: CircuitBreaker,
+    in_flight: AtomicUsize,
+}
+
+impl Endpoint {
+    fn new(url: &str, weight: u32, breaker: CircuitBreaker) -> Arc<Self> {
+        Arc::new(Endpoint {
+            url: url.trim_end_matches('/').to_string(),
+            weight: weight.max(1),
+            breaker,
+            in_flight: AtomicUsize::new(0),
+        })
+    }
+}
+
+// Counts a request in flight on an endpoint until dropped, cancelled requests included
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl<'a> InFlight<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        InFlight(counter)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// HTTP transport over one or more model servers, with timeouts, retries, failover and a circuit breaker per server
+#[derive(Clone)]
+pub(crate) struct ServerPool {
+    // Kind of server, for logs and errors
+    server: &'static str,
+    http: Client,
+    endpoints: Vec<Arc<Endpoint>>,
+    balance: Balance,
+    cursor: Arc<AtomicUsize>,
+    request_timeout: Duration,
+    retry: RetryPolicy,
+    // Sent as bearer token, OpenAI-compatible servers may require one
+    api_key: Option<String>,
+}
+
+impl ServerPool {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        server: &'static str,
+        endpoints: &[(String, u32)],
+        balance: Balance,
+        connect_timeout: Duration,
+        request_timeout: Duration,
+        retry: RetryPolicy,
+        breaker_threshold: u32,
+        breaker_cooldown: Duration
+    ) -> Result<Self> {
+        if endpoints.is_empty() {
+            bail!("No {} endpoint configured", server);
+        }
+        // Streamed responses have no total deadline, a stalled stream times out between reads instead
+        let http = Client::builder().connect_timeout(connect_timeout).read_timeout(request_timeout).build()?;
+
+        Ok(ServerPool {
+            server,
+            http,
+            endpoints: endpoints
+                .iter()
+                .map(|(url, weight)| Endpoint::new(url, *weight, CircuitBreaker::new(breaker_threshold, breaker_cooldown)))
+                .collect(),
+            balance,
+            cursor: Arc::new(AtomicUsize::new(0)),
+            request_timeout,
+            retry,
+            api_key: None,
+        })
+    }
+
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// Base URL of the server, comma separated URLs of a pool
+    pub fn url(&self) -> String {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.url.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// A pool per server, sharing its breaker and load, for per-server operations like pulls
+    pub fn split(&self) -> Vec<ServerPool> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| ServerPool {
+                endpoints: vec![endpoint.clone()],
+                cursor: Arc::new(AtomicUsize::new(0)),
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /// Requests worth sending in parallel to keep every server busy, the sum of the weights
+    pub fn concurrency(&self) -> usize {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.weight as usize)
+            .sum()
+    }
+
+    /// Whether both pools send to the same servers, sharing their breakers
+    pub fn shares_servers(&self, other: &ServerPool) -> bool {
+        self.endpoints.len() == other.endpoints.len() &&
+            self.endpoints
+                .iter()
+                .zip(&other.endpoints)
+                .all(|(endpoint, other)| Arc::ptr_eq(endpoint, other))
+    }
+
+    /// Underlying HTTP client, for calls to other local services
+    pub fn http(&self) -> &Client {
+        &self.http
+    }
+
+    /// Indexes of the endpoints whose circuit breaker is closed or half open
+    fn available(&self) -> Vec<usize> {
+        (0..self.endpoints.len()).filter(|index| self.endpoints[*index].breaker.open_for().is_none()).collect()
+    }
+
+    /// Next endpoint to send to, preferring the ones not `tried` by the current call
+    fn pick(&self, tried: &[usize]) -> Option<usize> {
+        let available = self.available();
+        let untried = available
+            .iter()
+            .copied()
+            .filter(|index| !tried.contains(index))
+            .collect::<Vec<_>>();
+        let candidates = if untried.is_empty() { available } else { untried };
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let turn = self.cursor.fetch_add(1, Ordering::Relaxed);
+        match self.balance {
+            Balance::RoundRobin => {
+                let total: usize = candidates.iter().map(|index| self.endpoints[*index].weight as usize).sum();
+                let mut slot = turn % total;
+                for index in &candidates {
+                    let weight = self.endpoints[*index].weight as usize;
+                    if slot < weight {
+                        return Some(*index);
+                    }
+                    slot -= weight;
+                }
+                None
+            }
+            // Ties go to the endpoints in turn
+            Balance::LeastLoaded =>
+                (0..candidates.len()).map(|offset| candidates[(turn + offset) % candidates.len()]).min_by_key(|index| {
+                    let endpoint = &self.endpoints[*index];
+                    ((endpoint.in_flight.load(Ordering::SeqCst) + 1) * 1000) / (endpoint.weight as usize)
+                }),
+        }
+    }
+
+    /// Sends a request built by `request` for an endpoint URL. Retryable failures count towards the
+    /// endpoint's circuit breaker and fail over to another endpoint, or back off when all were tried
+    pub async fn send(&self, stream: bool, request: impl Fn(&Client, &str) -> RequestBuilder) -> Result<Response> {
+        let mut tried = Vec::new();
+        let mut last_error: Option<anyhow::Error> = None;
+        let mut attempt = 0;
+        loop {
+            let Some(index) = self.pick(&tried) else {
+                let open_for = self.endpoints
+                    .iter()
+                    .filter_map(|endpoint| endpoint.breaker.open_for())
+                    .min()
+                    .unwrap_or_default();
+                let error = anyhow!("{} at {} is failing, circuit breaker open for another {:.0?}", self.server, self.url(), open_for);
+                return Err(match last_error {
+                    Some(e) => e.context(error),
+                    None => error,
+                });
+            };
+            let endpoint = &self.endpoints[index];
+
+            let result = {
+                let _in_flight = InFlight::new(&endpoint.in_flight);
+                let mut builder = request(&self.http, &endpoint.url);
+                if let Some(api_key) = &self.api_key {
+                    builder = builder.bearer_auth(api_key);
+                }
+                if !stream {
+                    builder = builder.timeout(self.request_timeout);
+                }
+                match builder.send().await {
+                    Ok(response) => check_status(response).await,
+                    Err(e) => Err(e.into()),
+                }
+            };
+
+            match result {
+                Ok(response) => {
+                    endpoint.breaker.record_success();
+                    return Ok(response);
+                }
+                Err(e) if is_retryable(&e) => {
+                    if endpoint.breaker.record_failure() {
+                        warn!("{} at {} keeps failing, opening its circuit breaker", self.server, endpoint.url);
+                    }
+                    if attempt >= self.retry.max_retries {
+                        return Err(e.context(format!("{} request to {} failed after {} attempt(s)", self.server, endpoint.url, attempt + 1)));
+                    }
+                    attempt += 1;
+                    if !tried.contains(&index) {
+                        tried.push(index);
+                    }
+
+                    if self.available().iter().any(|index| !tried.contains(index)) {
+                        warn!("{} request to {} failed ({:#}), failing over, retry {}/{}", self.server, endpoint.url, e, attempt, self.retry.max_retries);
+                    } else {
+                        let retry_after = e.downcast_ref::<StatusError>().and_then(|error| error.retry_after);
+                        let delay = self.retry.delay(attempt, retry_after);
+                        warn!("{} request to {} failed ({:#}), retry {}/{} in {:?}", self.server, endpoint.url, e, attempt, self.retry.max_retries, delay);
+                        tokio::time::sleep(delay).await;
+                    }
+                    last_error = Some(e);
+                }
+                Err(e) => {
+                    return Err(e.context(format!("{} request to {} failed", self.server, endpoint.url)));
+                }
+            }
+        }
+    }
+
+    /// Probes every endpoint once with a GET of `path` described by `describe`, opening the breaker of
+    /// the unreachable ones and closing it for the others
+    pub async fn check_health<F, Fut>(&self, path: &str, describe: F) -> Vec<(String, Result<String>)>
+        where F: Fn(Response) -> Fut, Fut: Future<Output = Result<String>>
+    {
+        let probes = self.endpoints.iter().map(|endpoint| {
+            let describe = &describe;
+            async move {
+                let result = async {
+                    let mut request = self.http.get(format!("{}{}", endpoint.url, path)).timeout(self.request_timeout);
+                    if let Some(api_key) = &self.api_key {
+                        request = request.bearer_auth(api_key);
+                    }
+                    describe(check_status(request.send().await?).await?).await
+                }.await;
+                match &result {
+                    Ok(_) => endpoint.breaker.record_success(),
+                    Err(_) => endpoint.breaker.trip(),
+                }
+                (endpoint.url.clone(), result)
+            }
+        });
+
+        futures::future::join_all(probes).await
+    }
+}
@@ -1,95 +1,1043 @@
-use anyhow::Result;
-use reqwest::Client;
+use anyhow::{ bail, Context, Result };
+use futures::stream::{ self, BoxStream, StreamExt, TryStreamExt };
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{ BufRead, Write };
+use std::path::{ Path, PathBuf };
+use std::time::{ Duration, Instant };
+use serde::Deserialize;
 use serde_json::json;
-use surrealdb::engine::remote::ws::{ Client as WsClient, Ws };
-use surrealdb::opt::auth::Root;
+use surrealdb::engine::any::Any;
 use surrealdb::sql::Thing;
 use surrealdb::Surreal;
-use tracing::{ info, warn };
+use tracing::{ debug, info, warn };
 use uuid::Uuid;
 use crate::rag_system::structures::Document;
-use crate::RAG_RETRIEVE_SIMILAR_DOCUMENT_CHUNKS;
+use crate::{
+    RAG_ANSWER_TOKEN_RESERVE,
+    RAG_BACKUP_FORMAT_VERSION,
+    RAG_CHARS_PER_TOKEN,
+    RAG_DEFAULT_COLLECTION,
+    RAG_EXPORT_FORMAT_VERSION,
+    RAG_NO_DOCUMENTS_ANSWER,
+    RAG_RETRIEVE_SIMILAR_DOCUMENT_CHUNKS,
+};
 
-use super::structures::{ DocumentMetaData, OllamaEmbeddingResponse, OllamaGenerationResponse };
+use super::acl::{ restrict, Identity };
+use super::api_keys::{ self, ApiKey, ApiScope };
+use super::connection::DbConnection;
+use super::filter::{ is_identifier, Filter };
+use super::backend::ModelBackend;
+use super::migrations::{ self, collection_schema, latest_version, MigrationStatus };
+use super::pool::check_status;
+use super::reranker::{ parse_relevance_score, relevance_prompt, RerankResponse, Reranker };
+use super::structures::{
+    BackupManifest,
+    Collection,
+    DocumentMetaData,
+    DocumentOrder,
+    DocumentTarget,
+    ExportChunk,
+    ExportHeader,
+    ExportRecord,
+    Generation,
+    GenerationChunk,
+    KnowledgeBaseStats,
+    ListOptions,
+    MetadataUpdate,
+    QueryOptions,
+    RagAnswer,
+    Scope,
+    SourceReference,
+    SourceStats,
+    SourceSummary,
+    Tenant,
+};
+
+#[derive(Debug, Deserialize)]
+struct Count {
+    count: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChunkLengths {
+    total: Option<usize>,
+    min: Option<usize>,
+    max: Option<usize>,
+    avg: Option<f64>,
+}
+
+/// Reads `DIMENSION n` from a vector index definition
+fn index_dimension(definition: &str) -> Option<u16> {
+    let (_, rest) = definition.split_once("DIMENSION ")?;
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+/// Number of leading chunks whose prompt fits the context window with room for the answer, at least one
+fn fit_context(query: &str, context_docs: &[Document], context_window: usize) -> usize {
+    let budget = context_window.saturating_sub(RAG_ANSWER_TOKEN_RESERVE) * RAG_CHARS_PER_TOKEN;
+    let mut used = rag_prompt(query, &[]).len();
+
+    context_docs
+        .iter()
+        .take_while(|doc| {
+            used += doc.content.len() + 2;
+            used <= budget
+        })
+        .count()
+        .max(1)
+        .min(context_docs.len())
+}
+
+/// Prompt answering a question from the retrieved context chunks
+fn rag_prompt(query: &str, context_docs: &[Document]) -> String {
+    let context = context_docs
+        .iter()
+        .map(|doc| doc.content.clone())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    format!(
+        "Context:\n{}\n\nQuestion: {}\n\nAnswer based on the context above. If the context doesn't contain enough information, say so:",
+        context,
+        query
+    )
+}
 
 // RAG System
+#[derive(Clone)]
 pub struct RagSystem {
-    db: Surreal<WsClient>,
-    pub ollama_http_client: Client,
-    ollama_url: String,
-    embedding_model: String,
+    db: Surreal<Any>,
+    connection: DbConnection,
+    // Clients of the embedding and generation backends, the same Ollama servers unless configured otherwise
+    pub embedder: ModelBackend,
+    pub generator: ModelBackend,
+    pub embedding_model: String,
     pub embedding_model_dimension: u16,
+    // Configured embedding model and dimension, for collections not in the registry yet
+    default_embedding_model: (String, u16),
     generation_model: String,
+    // Tokens the generation model accepts, retrieved context is trimmed to fit when known
+    context_window: Option<usize>,
+    reranker: Option<Reranker>,
+    // Caller identity, documents are restricted by their ACL when set
+    identity: Option<Identity>,
+    // Tenant whose namespace/database this connection uses, `None` for the configured one
+    pub tenant: Option<String>,
+    // Active collection and the table holding its documents
+    pub collection: String,
+    table: String,
 }
 
 impl RagSystem {
     pub async fn new(
-        db_url: &str,
-        db_pass: &str,
-        db_user: &str,
-        db_ns: &str,
-        db_db: &str,
-        ollama_url: &str,
+        connection: DbConnection,
+        embedder: ModelBackend,
+        generator: ModelBackend,
         embedding_model: &str,
         embedding_model_dimension: u16,
         generation_model: &str
     ) -> Result<Self> {
-        // Connect to SurrealDB
-        let db = Surreal::new::<Ws>(db_url).await?;
-        db.signin(Root {
-            username: db_user,
-            password: db_pass,
-        }).await?;
-        db.use_ns(db_ns.to_owned()).use_db(db_db.to_owned()).await?;
+        Self::new_scoped(connection, embedder, generator, embedding_model, embedding_model_dimension, generation_model, &Scope::default()).await
+    }
 
-        // Create HTTP client for Ollama
-        let ollama_http_client = Client::new();
+    // Connect scoped to a tenant and collection, verifying the embedding model they use once selected
+    pub async fn new_scoped(
+        connection: DbConnection,
+        embedder: ModelBackend,
+        generator: ModelBackend,
+        embedding_model: &str,
+        embedding_model_dimension: u16,
+        generation_model: &str,
+        scope: &Scope
+    ) -> Result<Self> {
+        let mut rag = Self::connect(connection, embedder, generator, embedding_model, embedding_model_dimension, generation_model).await?;
+        if let Some(tenant) = &scope.tenant {
+            rag = rag.select_tenant(tenant).await?;
+        }
+        if let Some(collection) = &scope.collection {
+            rag = rag.select_collection(collection).await?;
+        }
+
+        // Refuse to run against a collection built with another embedding model
+        if scope.verify {
+            rag.verify_embedding_model().await?;
+        }
+
+        // Budget prompts by the generation model's context window, OpenAI-compatible servers do not report it
+        match rag.generator.context_window(&rag.generation_model).await {
+            Ok(Some(context_window)) => {
+                info!("Generation model '{}' context window: {} tokens", rag.generation_model, context_window);
+                rag.context_window = Some(context_window);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Could not read the context length of '{}', prompts are not budgeted: {}", rag.generation_model, e),
+        }
 
         info!("RAG System initialized successfully");
 
-        Ok(RagSystem {
+        Ok(rag)
+    }
+
+    // Connect without verifying the embedding model, for diagnostics
+    pub async fn connect(
+        connection: DbConnection,
+        embedder: ModelBackend,
+        generator: ModelBackend,
+        embedding_model: &str,
+        embedding_model_dimension: u16,
+        generation_model: &str
+    ) -> Result<Self> {
+        // Connect to SurrealDB
+        let db = connection.connect().await?;
+        info!("Connected to SurrealDB at {} with {} auth", connection.endpoint(), connection.auth_method());
+
+        Self::open(db, connection, embedder, generator, embedding_model, embedding_model_dimension, generation_model).await
+    }
+
+    // Scope an open database to the default collection
+    async fn open(
+        db: Surreal<Any>,
+        connection: DbConnection,
+        embedder: ModelBackend,
+        generator: ModelBackend,
+        embedding_model: &str,
+        embedding_model_dimension: u16,
+        generation_model: &str
+    ) -> Result<Self> {
+        let rag = RagSystem {
             db,
-            ollama_http_client,
-            ollama_url: ollama_url.to_string(),
+            connection,
+            embedder,
+            generator,
             embedding_model: embedding_model.to_string(),
             generation_model: generation_model.to_string(),
             embedding_model_dimension,
-        })
+            default_embedding_model: (embedding_model.to_string(), embedding_model_dimension),
+            context_window: None,
+            reranker: None,
+            identity: None,
+            tenant: None,
+            collection: RAG_DEFAULT_COLLECTION.to_string(),
+            table: RAG_DEFAULT_COLLECTION.to_string(),
+        };
+
+        rag.select_collection(RAG_DEFAULT_COLLECTION).await
+    }
+
+    // Override the detected context window of the generation model
+    pub fn with_context_window(mut self, context_window: Option<usize>) -> Self {
+        self.context_window = context_window;
+        self
+    }
+
+    // Enable an optional re-ranking stage between retrieval and generation
+    pub fn with_reranker(mut self, reranker: Option<Reranker>) -> Self {
+        if let Some(reranker) = &reranker {
+            info!("Re-ranker enabled: {:?}", reranker);
+        }
+        self.reranker = reranker;
+        self
+    }
+
+    // Restrict retrieval and document access to what the identity may see
+    pub fn with_identity(mut self, identity: Option<Identity>) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    pub fn identity(&self) -> Option<&Identity> {
+        self.identity.as_ref()
+    }
+
+    // Refuse writes on a read-only record user connection instead of failing on table permissions
+    fn ensure_writable(&self) -> Result<()> {
+        if self.connection.auth.is_read_only() {
+            bail!("SurrealDB record users are read-only, run this command as a namespace or database user (APP_SURREAL_DB_AUTH)");
+        }
+
+        Ok(())
+    }
+
+    // Refuse writes to a collection being re-embedded, they would miss the copy or land in the removed table
+    async fn ensure_not_reembedding(&self) -> Result<()> {
+        if let Some(Collection { reembedding: Some(table), .. }) = self.get_collection(&self.collection).await? {
+            bail!("Collection '{}' is being re-embedded into table {}, retry once `rigrag reembed` finished", self.collection, table);
+        }
+
+        Ok(())
+    }
+
+    // Re-read the active collection's table and embedding model from the registry, long running servers
+    // pick up a re-embedded collection instead of the removed table
+    pub async fn refresh(&self) -> Result<RagSystem> {
+        let mut rag = self.clone();
+        if let Some(collection) = self.get_collection(&self.collection).await? {
+            if collection.table != rag.table {
+                info!("Collection '{}' moved to table {} with model {}", collection.name, collection.table, collection.embedding_model);
+                rag.table = collection.table;
+                rag.embedding_model = collection.embedding_model;
+                rag.embedding_model_dimension = collection.dimension;
+            }
+        }
+
+        Ok(rag)
     }
 
-    // Initialize database schema
+    // Initialize database schema, recording the embedding model and dimension of the collection
     pub async fn init_schema(&self, embedding_model_dimension: u16) -> Result<()> {
-        // Create documents table with vector index
-        self.db.query(
-            format!("
-                DEFINE TABLE documents SCHEMAFULL;
-                DEFINE FIELD id ON documents TYPE string;
-                DEFINE FIELD content ON documents TYPE string;
-                DEFINE FIELD embedding ON documents TYPE array<float>;
-                DEFINE FIELD metadata ON documents TYPE object;
-                DEFINE FIELD created_at ON documents TYPE string;
-                DEFINE INDEX embedding_idx ON documents FIELDS embedding MTREE DIMENSION {};
-                ", embedding_model_dimension)
-        ).await?;
-
-        info!("Database schema initialized with dimensions: {}", embedding_model_dimension);
+        self.ensure_writable()?;
+        if let Some(collection) = self.get_collection(&self.collection).await? {
+            if collection.embedding_model != self.embedding_model || collection.dimension != embedding_model_dimension {
+                bail!(
+                    "Collection '{}' is already initialized with embedding model '{}' ({} dimensions), refusing to re-initialize it with '{}' ({} dimensions)",
+                    collection.name,
+                    collection.embedding_model,
+                    collection.dimension,
+                    self.embedding_model,
+                    embedding_model_dimension
+                );
+            }
+        }
+
+        self.migrate_up().await?;
+        self.define_table(&self.table, embedding_model_dimension).await?;
+        self.register_collection(embedding_model_dimension).await?;
+
+        info!("Database schema initialized with model: {}, dimensions: {}", self.embedding_model, embedding_model_dimension);
         Ok(())
     }
 
-    // Generate embedding using Ollama
-    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        let response = self.ollama_http_client
-            .post(&format!("{}/api/embeddings", self.ollama_url))
-            .json(
-                &json!({
-                "model": self.embedding_model,
-                "prompt": text
-            })
+    // Record the active collection with its table, embedding model and dimension
+    async fn register_collection(&self, embedding_model_dimension: u16) -> Result<()> {
+        let _: Option<Collection> = self.db
+            .upsert(("collections", self.collection.as_str()))
+            .content(Collection {
+                name: self.collection.clone(),
+                table: self.table.clone(),
+                embedding_model: self.embedding_model.clone(),
+                dimension: embedding_model_dimension,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                reembedding: None,
+            }).await?;
+
+        Ok(())
+    }
+
+    // Apply pending schema migrations to the database and every collection table
+    pub async fn migrate_up(&self) -> Result<Vec<u32>> {
+        self.ensure_writable()?;
+        // Adopt a table created before collections were recorded
+        if self.get_collection(&self.collection).await?.is_none() {
+            let info: Option<serde_json::Value> = self.db.query("INFO FOR DB").await?.take(0)?;
+            if info.is_some_and(|info| info["tables"].get(&self.table).is_some()) {
+                let dimension = self.check_index_dimension().await?;
+                info!("Registering existing table {} as collection '{}'", self.table, self.collection);
+                self.register_collection(dimension).await?;
+            }
+        }
+
+        let collections = self.list_collections().await?;
+        let versions = migrations::up(&self.db, &collections).await?;
+
+        info!("Database schema is up to date, {} migration(s) applied", versions.len());
+        Ok(versions)
+    }
+
+    // Schema migrations with their applied state
+    pub async fn migration_status(&self) -> Result<Vec<MigrationStatus>> {
+        migrations::status(&self.db).await
+    }
+
+    // Probe the real embedding dimension returned by the provider
+    pub async fn probe_embedding_dimension(&self) -> Result<usize> {
+        let embedding = self.generate_embedding("dimension probe").await?;
+
+        Ok(embedding.len())
+    }
+
+    // Verify the embedding model of the active collection is the configured one and returns vectors of its dimension
+    pub async fn verify_embedding_model(&self) -> Result<()> {
+        self.check_configured_model()?;
+        if self.get_collection(&self.collection).await?.is_none() {
+            self.check_index_dimension().await?;
+        }
+        let dimension = self.probe_embedding_dimension().await?;
+        if dimension != (self.embedding_model_dimension as usize) {
+            if self.get_collection(&self.collection).await?.is_some() {
+                bail!(
+                    "Embedding model '{}' of collection '{}' returns vectors of {} dimensions, but the collection holds {}. Rebuild it with `rigrag --collection {} reembed --model {} --dimension {}`",
+                    self.embedding_model,
+                    self.collection,
+                    dimension,
+                    self.embedding_model_dimension,
+                    self.collection,
+                    self.embedding_model,
+                    dimension
+                );
+            }
+            bail!(
+                "Embedding model '{}' returns vectors of {} dimensions, but {} are configured. Set {}={}",
+                self.embedding_model,
+                dimension,
+                self.embedding_model_dimension,
+                self.embedder.api().setting("embedding_model_dimension"),
+                dimension
+            );
+        }
+
+        debug!("Embedding model '{}' verified with {} dimensions", self.embedding_model, dimension);
+        Ok(())
+    }
+
+    // Refuse to embed the default collection with a model other than the configured one, named collections record their own model
+    fn check_configured_model(&self) -> Result<()> {
+        let (model, dimension) = &self.default_embedding_model;
+        if self.collection != RAG_DEFAULT_COLLECTION || (&self.embedding_model, self.embedding_model_dimension) == (model, *dimension) {
+            return Ok(());
+        }
+        let tenant = self.tenant.as_ref().map(|tenant| format!("--tenant {} ", tenant)).unwrap_or_default();
+        let api = self.embedder.api();
+
+        bail!(
+            "Collection '{}' is embedded with '{}' ({} dimensions), but '{}' ({} dimensions) is configured. Move it to the configured model with `rigrag {}reembed --model {} --dimension {}`, or set {}={} and {}={}",
+            self.collection,
+            self.embedding_model,
+            self.embedding_model_dimension,
+            model,
+            dimension,
+            tenant,
+            model,
+            dimension,
+            api.setting("embedding_model"),
+            self.embedding_model,
+            api.setting("embedding_model_dimension"),
+            self.embedding_model_dimension
+        )
+    }
+
+    // Dimension of the vector index of the active table, refusing one built for another dimension than configured
+    async fn check_index_dimension(&self) -> Result<u16> {
+        let Some(dimension) = self.index_dimension(&self.table).await? else {
+            return Ok(self.embedding_model_dimension);
+        };
+        if dimension != self.embedding_model_dimension {
+            let api = self.embedder.api();
+            bail!(
+                "Table {} of collection '{}' has a vector index of {} dimensions, but '{}' ({} dimensions) is configured. Set {} and {}={} to the model it was built with, then move it with `rigrag reembed --model {} --dimension {}`",
+                self.table,
+                self.collection,
+                dimension,
+                self.embedding_model,
+                self.embedding_model_dimension,
+                api.setting("embedding_model"),
+                api.setting("embedding_model_dimension"),
+                dimension,
+                self.embedding_model,
+                self.embedding_model_dimension
+            );
+        }
+
+        Ok(dimension)
+    }
+
+    // Dimension of the vector index defined on a collection table, `None` when the table has no index
+    pub async fn index_dimension(&self, table: &str) -> Result<Option<u16>> {
+        if !is_identifier(table) {
+            bail!("Invalid table name '{}'", table);
+        }
+        let info: Option<serde_json::Value> = self.db.query(format!("INFO FOR TABLE {}", table)).await?.take(0)?;
+
+        Ok(info.and_then(|info| info["indexes"]["embedding_idx"].as_str().and_then(index_dimension)))
+    }
+
+    // Create documents table with vector index, at the latest schema version
+    async fn define_table(&self, table: &str, embedding_model_dimension: u16) -> Result<()> {
+        self.db.query(collection_schema(table, embedding_model_dimension)).await?.check()?;
+
+        Ok(())
+    }
+
+    // Get a collection by name
+    pub async fn get_collection(&self, name: &str) -> Result<Option<Collection>> {
+        let collection: Option<Collection> = self.db.select(("collections", name)).await?;
+
+        Ok(collection)
+    }
+
+    // List all collections
+    pub async fn list_collections(&self) -> Result<Vec<Collection>> {
+        let collections: Vec<Collection> = self.db.query("SELECT * FROM collections ORDER BY name").await?.take(0)?;
+
+        Ok(collections)
+    }
+
+    // Create a named collection with its own table, index dimension and embedding model
+    pub async fn create_collection(&self, name: &str, embedding_model: &str, embedding_model_dimension: u16) -> Result<Collection> {
+        self.ensure_writable()?;
+        if !is_identifier(name) {
+            bail!("Invalid collection name '{}', use letters, digits and underscores", name);
+        }
+        if self.get_collection(name).await?.is_some() {
+            bail!("Collection '{}' already exists", name);
+        }
+
+        let table = if name == RAG_DEFAULT_COLLECTION { name.to_string() } else { format!("{}_{}", RAG_DEFAULT_COLLECTION, name) };
+
+        // Probe the provider so the collection never records a dimension its model cannot produce
+        let mut probe = self.clone();
+        probe.embedding_model = embedding_model.to_string();
+        let dimension = probe.probe_embedding_dimension().await?;
+        if dimension != (embedding_model_dimension as usize) {
+            bail!("Embedding model '{}' returns vectors of {} dimensions, not {}. Use --dimension {}", embedding_model, dimension, embedding_model_dimension, dimension);
+        }
+        self.define_table(&table, embedding_model_dimension).await?;
+
+        let collection: Collection = self.db
+            .create(("collections", name))
+            .content(Collection {
+                name: name.to_string(),
+                table,
+                embedding_model: embedding_model.to_string(),
+                dimension: embedding_model_dimension,
+                created_at: chrono::Utc::now().to_rfc3339(),
+                reembedding: None,
+            }).await?
+            .ok_or_else(|| anyhow::anyhow!("Failed to create collection"))?;
+
+        info!("Collection created: {} (table: {}, model: {}, dimension: {})", name, collection.table, embedding_model, embedding_model_dimension);
+        Ok(collection)
+    }
+
+    // Drop a collection, removing its table and all of its documents
+    pub async fn drop_collection(&self, name: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let collection = self.get_collection(name).await?.ok_or_else(|| anyhow::anyhow!("Collection '{}' not found", name))?;
+
+        self.db
+            .query(format!("BEGIN TRANSACTION; REMOVE TABLE {}; DELETE $collection; COMMIT TRANSACTION;", collection.table))
+            .bind(("collection", Thing::from(("collections", name)))).await?
+            .check()?;
+
+        info!("Collection dropped: {}", name);
+        Ok(())
+    }
+
+    // Get a tenant by name
+    pub async fn get_tenant(&self, name: &str) -> Result<Option<Tenant>> {
+        let tenant: Option<Tenant> = self.db.select(("_tenants", name)).await?;
+
+        Ok(tenant)
+    }
+
+    // List all tenants
+    pub async fn list_tenants(&self) -> Result<Vec<Tenant>> {
+        let tenants: Vec<Tenant> = self.db.query("SELECT * OMIT id FROM _tenants ORDER BY name").await?.take(0)?;
+
+        Ok(tenants)
+    }
+
+    // Register a tenant with its own namespace/database and initialize its schema
+    pub async fn create_tenant(&self, name: &str, namespace: Option<&str>, database: Option<&str>) -> Result<Tenant> {
+        self.ensure_writable()?;
+        let tenant = Tenant {
+            name: name.to_string(),
+            namespace: namespace.unwrap_or(name).to_string(),
+            database: database.unwrap_or(&self.connection.database).to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        for identifier in [&tenant.name, &tenant.namespace, &tenant.database] {
+            if !is_identifier(identifier) {
+                bail!("Invalid tenant name, namespace or database '{}', use letters, digits and underscores", identifier);
+            }
+        }
+        if tenant.namespace == self.connection.namespace && tenant.database == self.connection.database {
+            bail!("Tenant '{}' cannot share the configured namespace/database {}/{}", name, tenant.namespace, tenant.database);
+        }
+        if self.get_tenant(name).await?.is_some() {
+            bail!("Tenant '{}' already exists", name);
+        }
+        if self.list_tenants().await?.iter().any(|other| other.namespace == tenant.namespace && other.database == tenant.database) {
+            bail!("Namespace/database {}/{} is already used by another tenant", tenant.namespace, tenant.database);
+        }
+
+        // Define the namespace and database on a connection of the tenant, then its schema
+        let rag = self.open_tenant(&tenant).await?;
+        rag.db
+            .query(format!("DEFINE NAMESPACE IF NOT EXISTS {}; DEFINE DATABASE IF NOT EXISTS {};", tenant.namespace, tenant.database)).await?
+            .check()?;
+        rag.init_schema(rag.embedding_model_dimension).await?;
+
+        let created: Option<Tenant> = self.db.create(("_tenants", name)).content(tenant).await?;
+        let created = created.ok_or_else(|| anyhow::anyhow!("Failed to register tenant"))?;
+
+        info!("Tenant created: {} ({}/{})", created.name, created.namespace, created.database);
+        Ok(created)
+    }
+
+    // Remove a tenant database and its registration
+    pub async fn drop_tenant(&self, name: &str) -> Result<()> {
+        self.ensure_writable()?;
+        let tenant = self.get_tenant(name).await?.ok_or_else(|| anyhow::anyhow!("Tenant '{}' not found", name))?;
+
+        let rag = self.open_tenant(&tenant).await?;
+        rag.db.query(format!("REMOVE DATABASE IF EXISTS {};", tenant.database)).await?.check()?;
+        let _: Option<Tenant> = self.db.delete(("_tenants", name)).await?;
+
+        info!("Tenant dropped: {}", name);
+        Ok(())
+    }
+
+    // Connect to the namespace/database of a tenant, scoped to its default collection
+    async fn open_tenant(&self, tenant: &Tenant) -> Result<RagSystem> {
+        let mut rag = self.clone();
+        rag.connection = DbConnection {
+            namespace: tenant.namespace.clone(),
+            database: tenant.database.clone(),
+            ..self.connection.clone()
+        };
+        rag.db = rag.connection.connect().await?;
+        rag.tenant = Some(tenant.name.clone());
+
+        rag.select_collection(RAG_DEFAULT_COLLECTION).await
+    }
+
+    // Open the RAG system of a tenant on its own connection, scoped to its default collection
+    pub async fn with_tenant(&self, name: &str) -> Result<RagSystem> {
+        let rag = self.select_tenant(name).await?;
+        rag.verify_embedding_model().await?;
+
+        Ok(rag)
+    }
+
+    // Open the connection of a registered tenant without verifying its embedding model
+    async fn select_tenant(&self, name: &str) -> Result<RagSystem> {
+        let tenant = self
+            .get_tenant(name).await?
+            .ok_or_else(|| anyhow::anyhow!("Tenant '{}' not found, create it with `rigrag tenant create {}`", name, name))?;
+
+        self.open_tenant(&tenant).await
+    }
+
+    // Re-embed the active collection with another model into a new table, then swap it in. Writes are refused
+    // while it runs, `force` takes over from a run that was interrupted
+    pub async fn reembed(&self, embedding_model: &str, embedding_model_dimension: u16, batch_size: usize, force: bool) -> Result<Collection> {
+        self.ensure_writable()?;
+        if self.migration_status().await?.iter().any(|migration| migration.applied_at.is_none()) {
+            bail!("Pending schema migrations, run `rigrag migrate up` before re-embedding");
+        }
+        let mut target = self.clone();
+        target.embedding_model = embedding_model.to_string();
+        target.embedding_model_dimension = embedding_model_dimension;
+        let dimension = target.probe_embedding_dimension().await?;
+        if dimension != (embedding_model_dimension as usize) {
+            bail!("Embedding model '{}' returns vectors of {} dimensions, not {}. Use --dimension {}", embedding_model, dimension, embedding_model_dimension, dimension);
+        }
+
+        let created_at = match self.get_collection(&self.collection).await? {
+            Some(Collection { reembedding: Some(table), .. }) if !force => {
+                bail!(
+                    "Collection '{}' is already being re-embedded into table {}. If that run was interrupted, take over with `rigrag reembed --force`",
+                    self.collection,
+                    table
+                );
+            }
+            Some(collection) => collection.created_at,
+            None => {
+                // Writers learn about the re-embed from the registry
+                self.register_collection(self.index_dimension(&self.table).await?.unwrap_or(self.embedding_model_dimension)).await?;
+                chrono::Utc::now().to_rfc3339()
+            }
+        };
+
+        target.table = format!("{}_{}_{}", RAG_DEFAULT_COLLECTION, self.collection, &Uuid::new_v4().simple().to_string()[..8]);
+        target.define_table(&target.table, embedding_model_dimension).await?;
+        self.db
+            .query("UPDATE type::thing('collections', $name) SET reembedding = $table")
+            .bind(("name", self.collection.clone()))
+            .bind(("table", target.table.clone())).await?
+            .check()?;
+        info!("Re-embedding collection '{}' from table {} into {} with model {}", self.collection, self.table, target.table, embedding_model);
+
+        if let Err(e) = self.copy_reembedded(&target, batch_size.max(1)).await {
+            // Keep the old table, discard the partial one
+            self.db
+                .query(format!("UPDATE type::thing('collections', $name) SET reembedding = NONE; REMOVE TABLE {};", target.table))
+                .bind(("name", self.collection.clone())).await?
+                .check()?;
+            return Err(e);
+        }
+
+        let collection = Collection {
+            name: self.collection.clone(),
+            table: target.table.clone(),
+            embedding_model: embedding_model.to_string(),
+            dimension: embedding_model_dimension,
+            created_at,
+            reembedding: None,
+        };
+        self.db
+            .query(
+                format!(
+                    "BEGIN TRANSACTION; UPSERT $collection CONTENT $content; REMOVE TABLE {}; COMMIT TRANSACTION;",
+                    self.table
+                )
             )
-            .send().await?;
+            .bind(("collection", Thing::from(("collections", self.collection.as_str()))))
+            .bind(("content", collection.clone())).await?
+            .check()?;
 
-        let embedding: OllamaEmbeddingResponse = response.json().await?;
-        Ok(embedding.embedding)
+        info!("Collection '{}' swapped to table {}", collection.name, collection.table);
+        Ok(collection)
+    }
+
+    // Copy every chunk of this table into the target table, embedding content in batches
+    async fn copy_reembedded(&self, target: &RagSystem, batch_size: usize) -> Result<()> {
+        let total = self.count_documents().await?;
+
+        let mut start = 0;
+        loop {
+            let documents = self.list_documents(&ListOptions {
+                start,
+                limit: batch_size,
+                order: DocumentOrder::Id,
+                with_embedding: false,
+            }).await?;
+            if documents.is_empty() {
+                break;
+            }
+
+            start += documents.len();
+            self.insert_reembedded(target, documents).await?;
+            info!("Re-embedded {}/{} chunks", start, total);
+        }
+
+        // Chunks of writers that checked the registry just before the re-embed was marked
+        let missing: Vec<Document> = self.db
+            .query(format!("SELECT * FROM {} WHERE record::id(id) NOTINSIDE (SELECT VALUE record::id(id) FROM {})", self.table, target.table)).await?
+            .take(0)?;
+        if !missing.is_empty() {
+            info!("Re-embedding {} chunk(s) stored during the copy", missing.len());
+            for documents in missing.chunks(batch_size) {
+                self.insert_reembedded(target, documents.to_vec()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Embed chunks with the target's model and insert them into its table
+    async fn insert_reembedded(&self, target: &RagSystem, mut documents: Vec<Document>) -> Result<()> {
+        let contents = documents
+            .iter()
+            .map(|doc| doc.content.clone())
+            .collect::<Vec<_>>();
+        let embeddings = target.generate_embeddings(&contents).await?;
+        if embeddings.len() != documents.len() {
+            bail!("Expected {} embeddings, got {}", documents.len(), embeddings.len());
+        }
+        for (doc, embedding) in documents.iter_mut().zip(embeddings) {
+            doc.id = Thing::from((target.table.as_str(), doc.id.id.clone()));
+            doc.embedding = embedding;
+        }
+
+        let _: Vec<Document> = self.db.insert(target.table.as_str()).content(documents).await?;
+        Ok(())
+    }
+
+    // Export the active collection as JSON Lines, a header followed by one chunk per line
+    pub async fn export_jsonl<W: Write>(&self, mut writer: W, batch_size: usize) -> Result<usize> {
+        let header = ExportRecord::Header(ExportHeader {
+            format_version: RAG_EXPORT_FORMAT_VERSION,
+            collection: self.collection.clone(),
+            embedding_model: self.embedding_model.clone(),
+            dimension: self.embedding_model_dimension,
+            schema_version: latest_version(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+        });
+        serde_json::to_writer(&mut writer, &header)?;
+        writeln!(writer)?;
+
+        let mut exported = 0;
+        loop {
+            let documents = self.list_documents(&ListOptions {
+                start: exported,
+                limit: batch_size.max(1),
+                order: DocumentOrder::Id,
+                with_embedding: true,
+            }).await?;
+            if documents.is_empty() {
+                break;
+            }
+            exported += documents.len();
+
+            for doc in documents {
+                let chunk = ExportRecord::Chunk(ExportChunk {
+                    id: doc.id.id.to_raw(),
+                    content: doc.content,
+                    metadata: doc.metadata,
+                    embedding: doc.embedding,
+                    created_at: doc.created_at,
+                });
+                serde_json::to_writer(&mut writer, &chunk)?;
+                writeln!(writer)?;
+            }
+            debug!("Exported {} chunks", exported);
+        }
+        writer.flush()?;
+
+        info!("Exported {} chunks from collection '{}'", exported, self.collection);
+        Ok(exported)
+    }
+
+    // Import a JSON Lines export into the active collection, keeping ids, embeddings and timestamps
+    pub async fn import_jsonl<R: BufRead>(&self, reader: R, batch_size: usize) -> Result<usize> {
+        self.ensure_writable()?;
+        self.ensure_not_reembedding().await?;
+        let mut lines = reader.lines();
+        let header = match lines.next() {
+            Some(line) =>
+                match serde_json::from_str::<ExportRecord>(&line?)? {
+                    ExportRecord::Header(header) => header,
+                    ExportRecord::Chunk(_) => bail!("Invalid export, the first line must be a header"),
+                }
+            None => bail!("Invalid export, the file is empty"),
+        };
+        if header.format_version > RAG_EXPORT_FORMAT_VERSION {
+            bail!("Unsupported export format version {}, this build reads up to {}", header.format_version, RAG_EXPORT_FORMAT_VERSION);
+        }
+        if header.embedding_model != self.embedding_model || header.dimension != self.embedding_model_dimension {
+            bail!(
+                "Export was embedded with '{}' ({} dimensions) but collection '{}' uses '{}' ({} dimensions). Import into a matching collection: `rigrag collection create <name> --model {} --dimension {}` then `rigrag --collection <name> import`",
+                header.embedding_model,
+                header.dimension,
+                self.collection,
+                self.embedding_model,
+                self.embedding_model_dimension,
+                header.embedding_model,
+                header.dimension
+            );
+        }
+
+        let mut imported = 0;
+        let mut batch = Vec::with_capacity(batch_size);
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<ExportRecord>(&line)? {
+                ExportRecord::Chunk(chunk) => {
+                    if chunk.embedding.len() != (self.embedding_model_dimension as usize) {
+                        bail!("Chunk {} has {} dimensions, expected {}", chunk.id, chunk.embedding.len(), self.embedding_model_dimension);
+                    }
+                    batch.push(Document {
+                        id: Thing::from((self.table.as_str(), chunk.id.as_str())),
+                        content: chunk.content,
+                        metadata: chunk.metadata,
+                        embedding: chunk.embedding,
+                        created_at: chunk.created_at,
+                    });
+                }
+                ExportRecord::Header(_) => bail!("Invalid export, unexpected header"),
+            }
+
+            if batch.len() >= batch_size.max(1) {
+                imported += self.insert_documents(std::mem::take(&mut batch)).await?;
+                debug!("Imported {} chunks", imported);
+            }
+        }
+        if !batch.is_empty() {
+            imported += self.insert_documents(batch).await?;
+        }
+
+        info!("Imported {} chunks into collection '{}' from '{}'", imported, self.collection, header.collection);
+        Ok(imported)
+    }
+
+    // Insert documents keeping their ids, chunks that already exist are left untouched
+    async fn insert_documents(&self, documents: Vec<Document>) -> Result<usize> {
+        let count = documents.len();
+        self.db
+            .query(format!("INSERT IGNORE INTO {} $documents RETURN NONE", self.table))
+            .bind(("documents", documents)).await?
+            .check()?;
+
+        Ok(count)
+    }
+
+    fn manifest_path(path: &Path) -> PathBuf {
+        PathBuf::from(format!("{}.manifest.json", path.display()))
+    }
+
+    // Back up the configured namespace/database with a manifest describing its contents
+    pub async fn backup(&self, path: &Path) -> Result<BackupManifest> {
+        let schema_version = self
+            .migration_status().await?
+            .iter()
+            .filter(|migration| migration.applied_at.is_some())
+            .map(|migration| migration.version)
+            .max()
+            .unwrap_or_default();
+        let collections = self.list_collections().await?;
+        let mut counts = BTreeMap::new();
+        for collection in &collections {
+            counts.insert(collection.name.clone(), self.count_table(&collection.table).await?);
+        }
+
+        self.connection.connect_http().await?.export(path).await?;
+
+        let manifest = BackupManifest {
+            format_version: RAG_BACKUP_FORMAT_VERSION,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            namespace: self.connection.namespace.clone(),
+            database: self.connection.database.clone(),
+            schema_version,
+            embedding_model: self.embedding_model.clone(),
+            dimension: self.embedding_model_dimension,
+            collections,
+            counts,
+        };
+        serde_json::to_writer_pretty(File::create(Self::manifest_path(path))?, &manifest)?;
+
+        info!("Backup of {}/{} written to {}", manifest.namespace, manifest.database, path.display());
+        Ok(manifest)
+    }
+
+    // Restore a backup after validating it is compatible with this build and configuration
+    pub async fn restore(&self, path: &Path, force: bool) -> Result<BackupManifest> {
+        self.ensure_writable()?;
+        let manifest_path = Self::manifest_path(path);
+        let manifest_file = File::open(&manifest_path).with_context(|| format!("Failed to open backup manifest {}", manifest_path.display()))?;
+        let manifest: BackupManifest = serde_json::from_reader(manifest_file)?;
+
+        if manifest.format_version > RAG_BACKUP_FORMAT_VERSION {
+            bail!("Unsupported backup format version {}, this build reads up to {}", manifest.format_version, RAG_BACKUP_FORMAT_VERSION);
+        }
+        if manifest.schema_version > latest_version() {
+            bail!("Backup has schema version {}, newer than the latest known by this build ({}), upgrade rigrag first", manifest.schema_version, latest_version());
+        }
+        if let Some(collection) = manifest.collections.iter().find(|collection| collection.name == self.collection) {
+            if collection.embedding_model != self.embedding_model || collection.dimension != self.embedding_model_dimension {
+                bail!(
+                    "Backup collection '{}' was embedded with '{}' ({} dimensions) but '{}' ({} dimensions) is configured. Set {}={} and {}={}",
+                    collection.name,
+                    collection.embedding_model,
+                    collection.dimension,
+                    self.embedding_model,
+                    self.embedding_model_dimension,
+                    self.embedder.api().setting("embedding_model"),
+                    collection.embedding_model,
+                    self.embedder.api().setting("embedding_model_dimension"),
+                    collection.dimension
+                );
+            }
+        }
+        if !force {
+            for collection in self.list_collections().await? {
+                let count = self.count_table(&collection.table).await?;
+                if count > 0 {
+                    bail!("Collection '{}' already has {} chunks, restore into an empty database or use --force", collection.name, count);
+                }
+            }
+        }
+
+        self.connection.connect_http().await?.import(path).await?;
+        self.migrate_up().await?;
+
+        info!("Backup {} restored into {}/{}", path.display(), self.connection.namespace, self.connection.database);
+        Ok(manifest)
+    }
+
+    // Create an API key, returning it with its secret
+    pub async fn create_api_key(
+        &self,
+        name: &str,
+        scopes: Vec<ApiScope>,
+        groups: Vec<String>,
+        tenant: Option<String>,
+        requests_per_day: Option<usize>,
+        tokens_per_day: Option<usize>
+    ) -> Result<(ApiKey, String)> {
+        self.ensure_writable()?;
+        if let Some(tenant) = &tenant {
+            if self.get_tenant(tenant).await?.is_none() {
+                bail!("Tenant '{}' not found", tenant);
+            }
+        }
+        api_keys::create(&self.db, name, scopes, groups, tenant, requests_per_day, tokens_per_day).await
+    }
+
+    // List API keys, including revoked ones
+    pub async fn list_api_keys(&self) -> Result<Vec<ApiKey>> {
+        api_keys::list(&self.db).await
+    }
+
+    // Revoke an API key by name
+    pub async fn revoke_api_key(&self, name: &str) -> Result<ApiKey> {
+        self.ensure_writable()?;
+        api_keys::revoke(&self.db, name).await
+    }
+
+    // Find the active API key for a secret
+    pub async fn authenticate_api_key(&self, secret: &str) -> Result<Option<ApiKey>> {
+        api_keys::authenticate(&self.db, secret).await
+    }
+
+    // Count a request against the daily quota of an API key, `None` when a quota is used up
+    pub async fn claim_api_key_request(&self, name: &str) -> Result<Option<ApiKey>> {
+        api_keys::claim_request(&self.db, name).await
+    }
+
+    // Add requests and generated tokens to the daily usage of an API key
+    pub async fn record_api_key_usage(&self, name: &str, requests: usize, tokens: usize) -> Result<()> {
+        api_keys::record_usage(&self.db, name, requests, tokens).await
+    }
+
+    // Check the database connection is alive
+    pub async fn health(&self) -> Result<()> {
+        self.db.health().await?;
+
+        Ok(())
+    }
+
+    // Scope the RAG system to a named collection, using its table and embedding model
+    pub async fn with_collection(&self, name: &str) -> Result<RagSystem> {
+        let rag = self.clone().select_collection(name).await?;
+        rag.verify_embedding_model().await?;
+
+        Ok(rag)
+    }
+
+    // Point at a collection's table, embedding model and dimension as recorded in the registry, without verifying them.
+    // The default collection falls back to its own table and the configured model until it is registered
+    async fn select_collection(mut self, name: &str) -> Result<RagSystem> {
+        match self.get_collection(name).await? {
+            Some(collection) => {
+                self.collection = collection.name;
+                self.table = collection.table;
+                self.embedding_model = collection.embedding_model;
+                self.embedding_model_dimension = collection.dimension;
+            }
+            None if name == RAG_DEFAULT_COLLECTION => {
+                self.collection = RAG_DEFAULT_COLLECTION.to_string();
+                self.table = RAG_DEFAULT_COLLECTION.to_string();
+                (self.embedding_model, self.embedding_model_dimension) = self.default_embedding_model.clone();
+            }
+            None => bail!("Collection '{}' not found, create it with `rigrag collection create {}`", name, name),
+        }
+
+        Ok(self)
+    }
+
+    // Generate embedding with the embedding backend
+    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.embedder.embedding(&self.embedding_model, text).await
+    }
+
+    // Generate embeddings for a batch of texts with the embedding backend
+    pub async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.embedder.embed(&self.embedding_model, texts).await
     }
 
     // Store document with embedding
@@ -98,12 +1046,14 @@ impl RagSystem {
         content: &str,
         metadata: DocumentMetaData
     ) -> Result<String> {
+        self.ensure_writable()?;
+        self.ensure_not_reembedding().await?;
         let embedding = self.generate_embedding(content).await?;
         let doc_id = Uuid::new_v4().to_string();
-        let record_id = ("documents", &doc_id);
+        let record_id = (self.table.as_str(), &doc_id);
 
         let doc = Document {
-            id: Thing::from(("documents", doc_id.as_str())),
+            id: Thing::from((self.table.as_str(), doc_id.as_str())),
             content: content.to_string(),
             embedding,
             metadata,
@@ -119,37 +1069,37 @@ impl RagSystem {
         Ok(created_doc.id.to_string())
     }
 
-    // Store multiple documents
+    // Store multiple documents, embedding as many at once as the embedding servers take
     pub async fn store_documents(
         &self,
         documents: Vec<(String, DocumentMetaData)>
     ) -> Result<Vec<String>> {
-        let mut doc_ids = Vec::new();
-
-        for (content, metadata) in documents {
-            // info!("metadata: {:?}", &metadata.clone());
-            let doc_id = self.store_document(&content, metadata).await?;
-            doc_ids.push(doc_id);
-        }
+        let doc_ids: Vec<String> = stream::iter(documents)
+            .map(|(content, metadata)| async move { self.store_document(&content, metadata).await })
+            .buffered(self.embedder.concurrency())
+            .try_collect().await?;
 
         info!("Stored {} documents", doc_ids.len());
         Ok(doc_ids)
     }
 
-    // Retrieve similar documents
-    pub async fn retrieve_similar(&self, query: &str, limit: usize) -> Result<Vec<Document>> {
+    // Retrieve similar documents, optionally restricted by a metadata filter
+    pub async fn retrieve_similar(&self, query: &str, limit: usize, filter: Option<&Filter>) -> Result<Vec<Document>> {
         let query_embedding = self.generate_embedding(query).await?;
+        let filter = restrict(filter.map(Filter::compile).transpose()?, self.identity.as_ref());
 
         // Using vector similarity search (cosine similarity)
         let results: Vec<Document> = self.db
             .query(
-                "
-                SELECT * FROM documents
-                WHERE vector::similarity::cosine(embedding, $embedding) > 0.5
+                format!("
+                SELECT * FROM type::table($table)
+                WHERE vector::similarity::cosine(embedding, $embedding) > 0.5 {}
                 ORDER BY similarity DESC
                 LIMIT $limit
-                "
+                ", filter.condition)
             )
+            .bind(filter.params)
+            .bind(("table", self.table.clone()))
             .bind(("embedding", query_embedding))
             .bind(("limit", limit)).await?
             .take(0)?;
@@ -164,71 +1114,708 @@ impl RagSystem {
         query: &str,
         context_docs: &[Document]
     ) -> Result<String> {
-        let context = context_docs
-            .iter()
-            .map(|doc| doc.content.clone())
-            .collect::<Vec<_>>()
-            .join("\n\n");
-
-        let prompt = format!(
-            "Context:\n{}\n\nQuestion: {}\n\nAnswer based on the context above. If the context doesn't contain enough information, say so:",
-            context,
-            query
-        );
-
-        let response = self.ollama_http_client
-            .post(&format!("{}/api/generate", self.ollama_url))
-            .json(
-                &json!({
-                "model": self.generation_model,
-                "prompt": prompt,
-                "stream": false
-            })
-            )
-            .send().await?;
+        self.generate(&rag_prompt(query, context_docs)).await
+    }
+
+    // Generate a completion for a raw prompt
+    async fn generate(&self, prompt: &str) -> Result<String> {
+        Ok(self.generate_completion(prompt).await?.text)
+    }
+
+    // Generate a completion for a raw prompt, with its token counts
+    async fn generate_completion(&self, prompt: &str) -> Result<Generation> {
+        self.generator.generate(&self.generation_model, prompt).await
+    }
+
+    // Stream a completion for a raw prompt
+    async fn generate_stream(&self, prompt: &str) -> Result<BoxStream<'static, Result<GenerationChunk>>> {
+        self.generator.generate_stream(&self.generation_model, prompt).await
+    }
+
+    // Re-rank retrieved documents against the query and keep the top N
+    pub async fn rerank(
+        &self,
+        query: &str,
+        documents: Vec<Document>,
+        reranker: &Reranker
+    ) -> Result<Vec<Document>> {
+        let mut scores = Vec::with_capacity(documents.len());
+
+        match reranker {
+            Reranker::Llm { concurrency, .. } => {
+                scores.resize(documents.len(), 0.0);
+                let prompts = documents.iter().map(|doc| relevance_prompt(query, &doc.content)).collect::<Vec<_>>();
+                let mut replies = stream::iter(prompts.into_iter().enumerate())
+                    .map(|(index, prompt)| async move { (index, self.generate(&prompt).await) })
+                    .buffer_unordered(*concurrency);
+                while let Some((index, reply)) = replies.next().await {
+                    let reply = reply?;
+                    scores[index] = parse_relevance_score(&reply).unwrap_or_else(|| {
+                        warn!("Could not parse relevance score from reply: {}", reply);
+                        0.0
+                    });
+                }
+            }
+            Reranker::Endpoint { url, model, .. } => {
+                let response = self.generator.http()
+                    .post(url)
+                    .json(
+                        &json!({
+                        "model": model,
+                        "query": query,
+                        "documents": documents.iter().map(|doc| doc.content.as_str()).collect::<Vec<_>>()
+                    })
+                    )
+                    .send().await?;
+
+                let reranked: RerankResponse = check_status(response).await?.json().await?;
+                scores.resize(documents.len(), f32::MIN);
+                for result in reranked.results {
+                    if let Some(score) = scores.get_mut(result.index) {
+                        *score = result.relevance_score;
+                    }
+                }
+            }
+        }
 
-        let generation: OllamaGenerationResponse = response.json().await?;
-        Ok(generation.response)
+        let mut scored = documents.into_iter().zip(scores).collect::<Vec<_>>();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.truncate(reranker.top_n());
+        for (doc, score) in &scored {
+            debug!("Re-ranked {} [{}#{}] score: {}", doc.id, doc.metadata.source, doc.metadata.index, score);
+        }
+
+        Ok(
+            scored
+                .into_iter()
+                .map(|(doc, _)| doc)
+                .collect()
+        )
     }
 
     // Complete RAG pipeline
     pub async fn query(&self, question: &str) -> Result<String> {
+        self.query_with_options(question, &QueryOptions::default()).await
+    }
+
+    // Complete RAG pipeline with query options (metadata filter)
+    pub async fn query_with_options(&self, question: &str, options: &QueryOptions) -> Result<String> {
+        Ok(self.query_with_sources(question, options).await?.answer)
+    }
+
+    // Retrieve the context chunks for a question, re-ranked when a re-ranker is configured
+    pub async fn retrieve_context(&self, question: &str, options: &QueryOptions) -> Result<Vec<Document>> {
         info!("Processing query: {}", question);
 
         // Step 1: Retrieve similar documents
-        let similar_docs = self.retrieve_similar(question, RAG_RETRIEVE_SIMILAR_DOCUMENT_CHUNKS).await?;
+        let started = Instant::now();
+        let mut similar_docs = self.retrieve_similar(question, RAG_RETRIEVE_SIMILAR_DOCUMENT_CHUNKS, options.filter.as_ref()).await?;
+        let retrieve_elapsed = started.elapsed();
 
         if similar_docs.is_empty() {
             warn!("No relevant documents found in the knowledge base");
-            return Ok("No relevant documents found in the knowledge base.".to_string());
+            return Ok(similar_docs);
         }
 
-        // Step 2: Generate response using retrieved context
-        let response = self.generate_response(question, &similar_docs).await?;
+        // Step 2: Optionally re-rank retrieved documents
+        let mut rerank_elapsed = Duration::ZERO;
+        if let Some(reranker) = &self.reranker {
+            let started = Instant::now();
+            let candidates = similar_docs.len();
+            similar_docs = self.rerank(question, similar_docs, reranker).await?;
+            rerank_elapsed = started.elapsed();
+            info!("Re-ranked {} candidate chunks, kept {}", candidates, similar_docs.len());
+        }
+
+        // Step 3: Keep the chunks that fit the generation model's context window
+        if let Some(context_window) = self.context_window {
+            let fitting = fit_context(question, &similar_docs, context_window);
+            if fitting < similar_docs.len() {
+                info!("Context window of {} tokens fits {} of {} chunks", context_window, fitting, similar_docs.len());
+                similar_docs.truncate(fitting);
+            }
+        }
 
-        info!("Generated response for query");
-        Ok(response)
+        info!("Retrieved context for query (retrieve: {:?}, rerank: {:?})", retrieve_elapsed, rerank_elapsed);
+        Ok(similar_docs)
+    }
+
+    // Complete RAG pipeline returning the answer with the chunks it is based on
+    pub async fn query_with_sources(&self, question: &str, options: &QueryOptions) -> Result<RagAnswer> {
+        let similar_docs = self.retrieve_context(question, options).await?;
+        if similar_docs.is_empty() {
+            return Ok(RagAnswer {
+                answer: RAG_NO_DOCUMENTS_ANSWER.to_string(),
+                sources: Vec::new(),
+                prompt_tokens: 0,
+                completion_tokens: 0,
+            });
+        }
+
+        // Step 3: Generate response using retrieved context
+        let started = Instant::now();
+        let generation = self.generate_completion(&rag_prompt(question, &similar_docs)).await?;
+        info!("Generated response for query (generate: {:?})", started.elapsed());
+
+        Ok(RagAnswer {
+            answer: generation.text,
+            sources: similar_docs.iter().map(SourceReference::from).collect(),
+            prompt_tokens: generation.prompt_tokens,
+            completion_tokens: generation.completion_tokens,
+        })
+    }
+
+    // Complete RAG pipeline streaming the answer, the sources are known before generation starts
+    pub async fn query_stream(
+        &self,
+        question: &str,
+        options: &QueryOptions
+    ) -> Result<(Vec<SourceReference>, BoxStream<'static, Result<GenerationChunk>>)> {
+        let similar_docs = self.retrieve_context(question, options).await?;
+        if similar_docs.is_empty() {
+            let answer = GenerationChunk { text: RAG_NO_DOCUMENTS_ANSWER.to_string(), done: true, ..Default::default() };
+            return Ok((Vec::new(), stream::iter([Ok(answer)]).boxed()));
+        }
+
+        let generation = self.generate_stream(&rag_prompt(question, &similar_docs)).await?;
+        Ok((similar_docs.iter().map(SourceReference::from).collect(), generation))
+    }
+
+    // Count documents in the active collection
+    pub async fn count_documents(&self) -> Result<usize> {
+        self.count_table(&self.table).await
+    }
+
+    async fn count_table(&self, table: &str) -> Result<usize> {
+        let count: Option<Count> = self.db
+            .query("SELECT count() FROM type::table($table) GROUP ALL")
+            .bind(("table", table.to_string())).await?
+            .take(0)?;
+
+        Ok(count.map(|count| count.count).unwrap_or_default())
+    }
+
+    // List sources with their chunk counts, paginated and ordered by source
+    pub async fn list_sources(&self, start: usize, limit: usize) -> Result<Vec<SourceSummary>> {
+        let acl = restrict(None, self.identity.as_ref());
+        let sources: Vec<SourceSummary> = self.db
+            .query(format!(
+                "SELECT metadata.source AS source, count() AS chunks FROM type::table($table) WHERE true {} GROUP BY source ORDER BY source LIMIT $limit START $start",
+                acl.condition
+            ))
+            .bind(acl.params)
+            .bind(("table", self.table.clone()))
+            .bind(("limit", limit))
+            .bind(("start", start)).await?
+            .take(0)?;
+
+        Ok(sources)
+    }
+
+    // Count distinct sources
+    pub async fn count_sources(&self) -> Result<usize> {
+        let acl = restrict(None, self.identity.as_ref());
+        let count: Option<Count> = self.db
+            .query(format!("SELECT count() FROM (SELECT metadata.source AS source FROM type::table($table) WHERE true {} GROUP BY source) GROUP ALL", acl.condition))
+            .bind(acl.params)
+            .bind(("table", self.table.clone())).await?
+            .take(0)?;
+
+        Ok(count.map(|count| count.count).unwrap_or_default())
+    }
+
+    // Get all chunks of a source ordered by index, without embeddings
+    pub async fn get_source(&self, source: &str) -> Result<Vec<Document>> {
+        let acl = restrict(None, self.identity.as_ref());
+        let documents: Vec<Document> = self.db
+            .query(format!("SELECT * OMIT embedding FROM type::table($table) WHERE metadata.source = $source {} ORDER BY metadata.index", acl.condition))
+            .bind(acl.params)
+            .bind(("table", self.table.clone()))
+            .bind(("source", source.to_string())).await?
+            .take(0)?;
+
+        Ok(documents)
+    }
+
+    // Delete a document or all chunks of a source in a single statement (one transaction)
+    pub async fn delete_documents(&self, target: &DocumentTarget) -> Result<usize> {
+        self.ensure_writable()?;
+        self.ensure_not_reembedding().await?;
+        let (what, condition, target) = match target {
+            DocumentTarget::Id(id) => ("type::thing($table, $target)", "WHERE true", id),
+            DocumentTarget::Source(source) => ("type::table($table)", "WHERE metadata.source = $target", source),
+        };
+        let acl = restrict(None, self.identity.as_ref());
+        let deleted: Option<usize> = self.db
+            .query(format!("RETURN array::len((DELETE {} {} {} RETURN BEFORE))", what, condition, acl.condition))
+            .bind(acl.params)
+            .bind(("table", self.table.clone()))
+            .bind(("target", target.clone())).await?
+            .take(0)?;
+        let deleted = deleted.unwrap_or_default();
+
+        info!("Deleted {} document(s) for {}", deleted, target);
+        Ok(deleted)
+    }
+
+    // Statistics of the active collection
+    pub async fn stats(&self) -> Result<KnowledgeBaseStats> {
+        let acl = restrict(None, self.identity.as_ref());
+        let mut response = self.db
+            .query(format!(
+                "
+                SELECT count() AS count FROM type::table($table) WHERE true {acl} GROUP ALL;
+                SELECT math::sum(string::len(content)) AS total, math::min(string::len(content)) AS min, math::max(string::len(content)) AS max, math::mean(string::len(content)) AS avg FROM type::table($table) WHERE true {acl} GROUP ALL;
+                SELECT metadata.source AS source, count() AS chunks, math::sum(string::len(content)) AS characters FROM type::table($table) WHERE true {acl} GROUP BY source ORDER BY source;
+                SELECT VALUE created_at FROM type::table($table) WHERE true {acl} ORDER BY created_at ASC LIMIT 1;
+                SELECT VALUE created_at FROM type::table($table) WHERE true {acl} ORDER BY created_at DESC LIMIT 1;
+                ",
+                acl = acl.condition
+            ))
+            .bind(acl.params)
+            .bind(("table", self.table.clone())).await?;
+        let count: Option<Count> = response.take(0)?;
+        let chunks = count.map(|count| count.count).unwrap_or_default();
+        let lengths: Option<ChunkLengths> = response.take(1)?;
+        let lengths = lengths.unwrap_or_default();
+        let sources: Vec<SourceStats> = response.take(2)?;
+        let first_created_at: Option<String> = response.take(3)?;
+        let last_created_at: Option<String> = response.take(4)?;
+
+        // Vector index definition, e.g. `MTREE DIMENSION 768 DIST EUCLIDEAN`
+        let table_info: Option<serde_json::Value> = self.db.query(format!("INFO FOR TABLE {}", self.table)).await?.take(0)?;
+        let index = table_info
+            .and_then(|info| info["indexes"]["embedding_idx"].as_str().map(str::to_string))
+            .map(|definition| {
+                let start = definition.find("MTREE").or_else(|| definition.find("HNSW")).unwrap_or_default();
+                definition[start..].trim().to_string()
+            });
+
+        let total_characters = lengths.total.unwrap_or_default();
+        let estimated_content_bytes = total_characters as u64;
+        let estimated_embedding_bytes = (chunks as u64) * (self.embedding_model_dimension as u64) * 8;
+        let estimated_index_bytes = estimated_embedding_bytes;
+
+        Ok(KnowledgeBaseStats {
+            collection: self.collection.clone(),
+            table: self.table.clone(),
+            embedding_model: self.embedding_model.clone(),
+            dimension: self.embedding_model_dimension,
+            index,
+            chunks,
+            total_characters,
+            min_chunk_length: lengths.min.unwrap_or_default(),
+            max_chunk_length: lengths.max.unwrap_or_default(),
+            avg_chunk_length: lengths.avg.unwrap_or_default(),
+            first_created_at,
+            last_created_at,
+            estimated_content_bytes,
+            estimated_embedding_bytes,
+            estimated_index_bytes,
+            estimated_total_bytes: estimated_content_bytes + estimated_embedding_bytes + estimated_index_bytes,
+            sources,
+        })
     }
 
     // Get document by ID
     pub async fn get_document(&self, doc_id: &str) -> Result<Option<Document>> {
-        let result: Option<Document> = self.db.select(("documents", doc_id)).await?;
+        let acl = restrict(None, self.identity.as_ref());
+        let result: Option<Document> = self.db
+            .query(format!("SELECT * FROM type::thing($table, $id) WHERE true {}", acl.condition))
+            .bind(acl.params)
+            .bind(("table", self.table.clone()))
+            .bind(("id", doc_id.to_string())).await?
+            .take(0)?;
 
         Ok(result)
     }
 
-    // List all documents
-    pub async fn list_documents(&self) -> Result<Vec<Document>> {
-        let documents: Vec<Document> = self.db.select("documents").await?;
+    // List a page of documents, embeddings are omitted unless requested
+    pub async fn list_documents(&self, options: &ListOptions) -> Result<Vec<Document>> {
+        let projection = if options.with_embedding { "*" } else { "* OMIT embedding" };
+        let order = match options.order {
+            DocumentOrder::Source => "metadata.source, metadata.index",
+            DocumentOrder::CreatedAt => "created_at",
+            DocumentOrder::Id => "id",
+        };
+        let acl = restrict(None, self.identity.as_ref());
+        let documents: Vec<Document> = self.db
+            .query(format!("SELECT {} FROM type::table($table) WHERE true {} ORDER BY {} LIMIT $limit START $start", projection, acl.condition, order))
+            .bind(acl.params)
+            .bind(("table", self.table.clone()))
+            .bind(("limit", options.limit))
+            .bind(("start", options.start)).await?
+            .take(0)?;
 
         Ok(documents)
     }
 
-    // Delete document
+    // Update user metadata (tags and attributes) of existing documents
+    pub async fn update_metadata(&self, target: &DocumentTarget, update: &MetadataUpdate) -> Result<usize> {
+        self.ensure_writable()?;
+        self.ensure_not_reembedding().await?;
+        let mut assignments = vec![
+            "metadata.tags = array::complement(array::union(metadata.tags ?? [], $add_tags), $remove_tags)".to_string(),
+            "metadata.groups = array::complement(array::union(metadata.groups ?? [], $add_groups), $remove_groups)".to_string()
+        ];
+        match &update.owner {
+            Some(Some(_)) => assignments.push("metadata.owner = $owner".to_string()),
+            Some(None) => assignments.push("metadata.owner = NONE".to_string()),
+            None => {}
+        }
+        let mut attributes = BTreeMap::new();
+        for (key, value) in &update.set_attributes {
+            if !is_identifier(key) {
+                bail!("Invalid metadata key '{}'", key);
+            }
+            let param = format!("attribute_{}", attributes.len());
+            assignments.push(format!("metadata.attributes.{} = ${}", key, param));
+            attributes.insert(param, value.clone());
+        }
+        for key in &update.unset_attributes {
+            if !is_identifier(key) {
+                bail!("Invalid metadata key '{}'", key);
+            }
+            assignments.push(format!("metadata.attributes.{} = NONE", key));
+        }
+
+        let (what, condition, target) = match target {
+            DocumentTarget::Id(id) => ("type::thing($table, $target)", "WHERE true", id),
+            DocumentTarget::Source(source) => ("type::table($table)", "WHERE metadata.source = $target", source),
+        };
+        let acl = restrict(None, self.identity.as_ref());
+        let updated: Vec<Thing> = self.db
+            .query(format!("UPDATE {} SET {} {} {} RETURN VALUE id", what, assignments.join(", "), condition, acl.condition))
+            .bind(attributes)
+            .bind(acl.params)
+            .bind(("add_tags", update.add_tags.clone()))
+            .bind(("remove_tags", update.remove_tags.clone()))
+            .bind(("add_groups", update.add_groups.clone()))
+            .bind(("remove_groups", update.remove_groups.clone()))
+            .bind(("owner", update.owner.clone().flatten()))
+            .bind(("table", self.table.clone()))
+            .bind(("target", target.clone())).await?
+            .take(0)?;
+
+        info!("Updated metadata of {} document(s)", updated.len());
+        Ok(updated.len())
+    }
+
+    // Delete document, documents the identity may not see are left untouched
     pub async fn delete_document(&self, doc_id: &str) -> Result<()> {
-        let _: Option<Document> = self.db.delete(("documents", doc_id)).await?;
+        self.ensure_writable()?;
+        self.ensure_not_reembedding().await?;
+        let acl = restrict(None, self.identity.as_ref());
+        self.db
+            .query(format!("DELETE type::thing($table, $id) WHERE true {}", acl.condition))
+            .bind(acl.params)
+            .bind(("table", self.table.clone()))
+            .bind(("id", doc_id.to_string())).await?
+            .check()?;
 
         info!("Document deleted: {}", doc_id);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag_system::connection::DbAuth;
+    use crate::rag_system::ollama::OllamaClient;
+    use crate::rag_system::pool::Balance;
+    use crate::rag_system::resilience::RetryPolicy;
+    use crate::UserMetaData;
+    use axum::routing::post;
+    use axum::{ Json, Router };
+    use std::sync::atomic::{ AtomicUsize, Ordering };
+
+    // Generations the Ollama stub is answering, and the most at once
+    static GENERATING: AtomicUsize = AtomicUsize::new(0);
+    static MOST_GENERATING: AtomicUsize = AtomicUsize::new(0);
+
+    /// Ollama stub embedding a text as its length followed by ones, `large` models return 4 dimensions, others 3.
+    /// Generations take a while and rate passages mentioning "pertinent" 9, others 2
+    async fn embedder() -> ModelBackend {
+        fn embedding(model: &str, text: &str) -> Vec<f32> {
+            let dimension = if model.contains("large") { 4 } else { 3 };
+            std::iter::once(text.len() as f32).chain(std::iter::repeat_n(1.0, dimension - 1)).collect()
+        }
+        let router = Router::new()
+            .route(
+                "/api/embeddings",
+                post(|Json(request): Json<serde_json::Value>| async move {
+                    Json(json!({ "embedding": embedding(request["model"].as_str().unwrap(), request["prompt"].as_str().unwrap()) }))
+                })
+            )
+            .route(
+                "/api/embed",
+                post(|Json(request): Json<serde_json::Value>| async move {
+                    let model = request["model"].as_str().unwrap();
+                    let embeddings = request["input"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|text| embedding(model, text.as_str().unwrap()))
+                        .collect::<Vec<_>>();
+                    Json(json!({ "embeddings": embeddings }))
+                })
+            )
+            .route(
+                "/api/generate",
+                post(|Json(request): Json<serde_json::Value>| async move {
+                    MOST_GENERATING.fetch_max(GENERATING.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    GENERATING.fetch_sub(1, Ordering::SeqCst);
+                    let score = if request["prompt"].as_str().unwrap().contains("pertinent") { "9" } else { "2" };
+                    Json(json!({ "response": score, "done": true }))
+                })
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let retry = RetryPolicy { max_retries: 0, base_delay: Duration::from_millis(1), max_delay: Duration::from_millis(1) };
+        ModelBackend::Ollama(OllamaClient::new(&[(url, 1)], Balance::RoundRobin, Duration::from_secs(1), Duration::from_secs(5), retry, 0, Duration::from_secs(1)).unwrap())
+    }
+
+    /// In-memory database
+    async fn database() -> Surreal<Any> {
+        let db = surrealdb::engine::any::connect("mem://").await.unwrap();
+        db.use_ns("rag").use_db("documents").await.unwrap();
+        db
+    }
+
+    /// Connects to `db` like a fresh process would, configured with the 3 dimensions `small` model
+    async fn open(db: &Surreal<Any>, embedder: &ModelBackend) -> RagSystem {
+        let connection = DbConnection {
+            url: "mem://".to_string(),
+            username: "root".to_string(),
+            password: "root".to_string(),
+            namespace: "rag".to_string(),
+            database: "documents".to_string(),
+            auth: DbAuth::Root,
+            ca_cert: None,
+        };
+        RagSystem::open(db.clone(), connection, embedder.clone(), embedder.clone(), "small", 3, "llama3.2").await.unwrap()
+    }
+
+    fn chunk(content: &str, user_metadata: &UserMetaData) -> (String, DocumentMetaData) {
+        (content.to_string(), DocumentMetaData::new(0, "test.pdf", user_metadata))
+    }
+
+    fn document(content: &str) -> Document {
+        Document {
+            id: Thing::from(("documents", "test")),
+            content: content.to_string(),
+            embedding: Vec::new(),
+            metadata: DocumentMetaData::new(0, "test.pdf", &UserMetaData::default()),
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_fit_context() {
+        let docs = vec![document(&"a".repeat(4000)), document(&"b".repeat(4000)), document(&"c".repeat(4000))];
+        assert_eq!(fit_context("question", &docs, 8192), 3);
+        assert_eq!(fit_context("question", &docs, 2048), 1);
+        assert_eq!(fit_context("question", &docs, 600), 1);
+        assert_eq!(fit_context("question", &[], 600), 0);
+    }
+
+    #[test]
+    fn test_index_dimension() {
+        assert_eq!(index_dimension("DEFINE INDEX embedding_idx ON documents FIELDS embedding MTREE DIMENSION 1024 DIST EUCLIDEAN CAPACITY 40"), Some(1024));
+        assert_eq!(index_dimension("DEFINE INDEX name_idx ON rag_users FIELDS name UNIQUE"), None);
+    }
+
+    #[tokio::test]
+    async fn test_reembed_then_connect() {
+        let (db, embedder) = (database().await, embedder().await);
+        let rag = open(&db, &embedder).await;
+        rag.init_schema(3).await.unwrap();
+        rag.store_documents(vec![chunk("first chunk", &UserMetaData::default()), chunk("second", &UserMetaData::default())]).await.unwrap();
+
+        let collection = rag.reembed("large", 4, 1, false).await.unwrap();
+
+        // A new connection finds the re-embedded table and model through the registry, and refuses the old configured model
+        let rag = open(&db, &embedder).await;
+        assert_eq!(rag.table, collection.table);
+        assert_eq!((rag.embedding_model.as_str(), rag.embedding_model_dimension), ("large", 4));
+        let error = rag.verify_embedding_model().await.unwrap_err().to_string();
+        assert!(error.contains("`rigrag reembed --model small --dimension 3`"), "{}", error);
+        let rag = RagSystem::open(db.clone(), rag.connection.clone(), embedder.clone(), embedder.clone(), "large", 4, "llama3.2").await.unwrap();
+        rag.verify_embedding_model().await.unwrap();
+        assert_eq!(rag.count_documents().await.unwrap(), 2);
+        assert_eq!(rag.retrieve_similar("first chunk", 2, None).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_follows_reembed() {
+        let (db, embedder) = (database().await, embedder().await);
+        let rag = open(&db, &embedder).await;
+        rag.init_schema(3).await.unwrap();
+        rag.store_documents(vec![chunk("first chunk", &UserMetaData::default())]).await.unwrap();
+
+        // A server opened before the re-embed still points at the removed table
+        let serving = rag.clone();
+        let collection = rag.reembed("large", 4, 1, false).await.unwrap();
+        let serving = serving.refresh().await.unwrap();
+        assert_eq!((serving.table.as_str(), serving.embedding_model.as_str()), (collection.table.as_str(), "large"));
+        assert_eq!(serving.retrieve_similar("first chunk", 2, None).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_writes_refused_while_reembedding() {
+        let (db, embedder) = (database().await, embedder().await);
+        let rag = open(&db, &embedder).await;
+        rag.init_schema(3).await.unwrap();
+        rag.store_documents(vec![chunk("first chunk", &UserMetaData::default())]).await.unwrap();
+        db.query("UPDATE collections:documents SET reembedding = 'documents_documents_interrupted'").await.unwrap().check().unwrap();
+
+        let error = rag.store_documents(vec![chunk("second", &UserMetaData::default())]).await.unwrap_err().to_string();
+        assert!(error.contains("is being re-embedded into table documents_documents_interrupted"), "{}", error);
+        assert!(rag.delete_documents(&DocumentTarget::Source("test.pdf".to_string())).await.is_err());
+        assert!(rag.reembed("large", 4, 1, false).await.unwrap_err().to_string().contains("--force"));
+
+        let collection = rag.reembed("large", 4, 1, true).await.unwrap();
+        assert_eq!(collection.reembedding, None);
+        let rag = rag.refresh().await.unwrap();
+        rag.store_documents(vec![chunk("second", &UserMetaData::default())]).await.unwrap();
+        assert_eq!(rag.count_documents().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_up_registers_index_dimension() {
+        let (db, embedder) = (database().await, embedder().await);
+        let rag = open(&db, &embedder).await;
+        // Table of 4 dimensions created before collections were recorded
+        rag.define_table(RAG_DEFAULT_COLLECTION, 4).await.unwrap();
+
+        let error = rag.migrate_up().await.unwrap_err().to_string();
+        assert!(error.contains("has a vector index of 4 dimensions, but 'small' (3 dimensions) is configured"), "{}", error);
+        assert!(rag.verify_embedding_model().await.is_err());
+        assert!(rag.get_collection(RAG_DEFAULT_COLLECTION).await.unwrap().is_none());
+
+        let rag = RagSystem::open(db.clone(), rag.connection.clone(), embedder.clone(), embedder.clone(), "large", 4, "llama3.2").await.unwrap();
+        rag.migrate_up().await.unwrap();
+        assert_eq!(rag.get_collection(RAG_DEFAULT_COLLECTION).await.unwrap().map(|collection| collection.dimension), Some(4));
+        rag.verify_embedding_model().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_exists_filter_skips_empty_tags_and_attributes() {
+        let (db, embedder) = (database().await, embedder().await);
+        let rag = open(&db, &embedder).await;
+        rag.init_schema(3).await.unwrap();
+        let tagged = UserMetaData { tags: vec!["sf".to_string()], attributes: json!({ "year": 1956 }).as_object().unwrap().clone(), ..Default::default() };
+        rag.store_documents(vec![chunk("tagged chunk", &tagged), chunk("plain chunk", &UserMetaData::default())]).await.unwrap();
+
+        for field in ["tags", "attributes", "attributes.year"] {
+            let filter = Filter::Exists { field: field.to_string() };
+            let documents = rag.retrieve_similar("chunk", 10, Some(&filter)).await.unwrap();
+            assert_eq!(documents.iter().map(|document| document.content.as_str()).collect::<Vec<_>>(), vec!["tagged chunk"], "{}?", field);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exists_filter_skips_missing_tags_and_attributes() {
+        let (db, embedder) = (database().await, embedder().await);
+        let rag = open(&db, &embedder).await;
+        rag.init_schema(3).await.unwrap();
+        rag.store_documents(vec![chunk("plain chunk", &UserMetaData::default())]).await.unwrap();
+        // Chunk without the fields, as stored before migration 2 backfilled them
+        db.query(
+            "REMOVE FIELD metadata.tags ON documents; REMOVE FIELD metadata.attributes ON documents;
+            UPDATE documents SET metadata.tags = NONE, metadata.attributes = NONE;"
+        ).await.unwrap()
+            .check().unwrap();
+
+        for field in ["tags", "attributes"] {
+            let filter = Filter::Exists { field: field.to_string() };
+            assert!(rag.retrieve_similar("chunk", 10, Some(&filter)).await.unwrap().is_empty(), "{}?", field);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_llm_rerank_scores_concurrently() {
+        let (db, embedder) = (database().await, embedder().await);
+        let rag = open(&db, &embedder).await;
+        let documents = ["first", "second pertinent", "third", "fourth", "fifth pertinent"].map(document).to_vec();
+
+        let reranked = rag.rerank("question", documents, &(Reranker::Llm { top_n: 2, concurrency: 2 })).await.unwrap();
+        let mut contents = reranked.iter().map(|doc| doc.content.as_str()).collect::<Vec<_>>();
+        contents.sort();
+        assert_eq!(contents, vec!["fifth pertinent", "second pertinent"]);
+        assert_eq!(MOST_GENERATING.load(Ordering::SeqCst), 2);
+    }
+
+    /// Collection holding one chunk owned by `alice`, seen through the identities of `alice` and `bob`
+    async fn owned_by_alice() -> (RagSystem, RagSystem) {
+        let (db, embedder) = (database().await, embedder().await);
+        let rag = open(&db, &embedder).await;
+        rag.init_schema(3).await.unwrap();
+        let owner = UserMetaData { owner: Some("alice".to_string()), ..Default::default() };
+        rag.store_documents(vec![chunk("private chunk", &owner)]).await.unwrap();
+
+        let identity = |user: &str| Some(Identity { user: user.to_string(), groups: Vec::new() });
+        (rag.clone().with_identity(identity("alice")), rag.with_identity(identity("bob")))
+    }
+
+    #[tokio::test]
+    async fn test_list_sources_hides_foreign_documents() {
+        let (owner, other) = owned_by_alice().await;
+        assert_eq!(owner.list_sources(0, 10).await.unwrap().len(), 1);
+        assert!(other.list_sources(0, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_count_sources_hides_foreign_documents() {
+        let (owner, other) = owned_by_alice().await;
+        assert_eq!(owner.count_sources().await.unwrap(), 1);
+        assert_eq!(other.count_sources().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stats_hide_foreign_documents() {
+        let (owner, other) = owned_by_alice().await;
+        let stats = owner.stats().await.unwrap();
+        assert_eq!((stats.chunks, stats.sources.len()), (1, 1));
+        let stats = other.stats().await.unwrap();
+        assert_eq!((stats.chunks, stats.total_characters, stats.sources.len()), (0, 0, 0));
+        assert_eq!(stats.first_created_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_list_documents_hides_foreign_documents() {
+        let (owner, other) = owned_by_alice().await;
+        assert_eq!(owner.list_documents(&ListOptions::default()).await.unwrap().len(), 1);
+        assert!(other.list_documents(&ListOptions::default()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_document_keeps_foreign_documents() {
+        let (owner, other) = owned_by_alice().await;
+        let id = owner.list_documents(&ListOptions::default()).await.unwrap()[0].id.id.to_raw();
+
+        other.delete_document(&id).await.unwrap();
+        assert_eq!(owner.count_documents().await.unwrap(), 1);
+        owner.delete_document(&id).await.unwrap();
+        assert_eq!(owner.count_documents().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_record_users_are_read_only() {
+        let (db, embedder) = (database().await, embedder().await);
+        let mut rag = open(&db, &embedder).await;
+        rag.init_schema(3).await.unwrap();
+        rag.store_documents(vec![chunk("first chunk", &UserMetaData::default())]).await.unwrap();
+
+        rag.connection.auth = DbAuth::Record { access: "rag_user".to_string() };
+        assert!(rag.store_documents(vec![chunk("second", &UserMetaData::default())]).await.is_err());
+        assert!(rag.delete_documents(&DocumentTarget::Source("test.pdf".to_string())).await.is_err());
+        assert!(rag.create_collection("hr", "small", 3).await.is_err());
+        assert_eq!(rag.count_documents().await.unwrap(), 1);
+        assert_eq!(rag.get_collection(RAG_DEFAULT_COLLECTION).await.unwrap().map(|collection| collection.dimension), Some(3));
+    }
+}
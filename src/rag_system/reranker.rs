@@ -0,0 +1,96 @@
+use anyhow::{ bail, Result };
+use serde::Deserialize;
+
+use crate::Configuration;
+
+// Re-ranking stage applied between retrieval and generation
+#[derive(Debug, Clone)]
+pub enum Reranker {
+    /// Pointwise relevance scoring using the generation model
+    Llm {
+        top_n: usize,
+        // Passages scored in parallel
+        concurrency: usize,
+    },
+    /// Dedicated re-ranker model served by a local endpoint (`/v1/rerank` style API)
+    Endpoint {
+        url: String,
+        model: String,
+        top_n: usize,
+    },
+}
+
+impl Reranker {
+    pub fn from_config(cfg: &Configuration) -> Result<Option<Self>> {
+        let top_n = cfg.reranker_top_n;
+        match cfg.reranker.as_str() {
+            "" | "none" => Ok(None),
+            "llm" => Ok(Some(Reranker::Llm { top_n, concurrency: cfg.reranker_concurrency })),
+            "endpoint" =>
+                Ok(
+                    Some(Reranker::Endpoint {
+                        url: cfg.reranker_url.clone(),
+                        model: cfg.reranker_model.clone(),
+                        top_n,
+                    })
+                ),
+            other => bail!("Unknown reranker '{}', expected one of: none, llm, endpoint", other),
+        }
+    }
+
+    pub fn top_n(&self) -> usize {
+        match self {
+            Reranker::Llm { top_n, .. } => *top_n,
+            Reranker::Endpoint { top_n, .. } => *top_n,
+        }
+    }
+}
+
+// Data structures for re-ranker endpoint API
+#[derive(Debug, Deserialize)]
+pub struct RerankResponse {
+    pub results: Vec<RerankResult>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RerankResult {
+    pub index: usize,
+    pub relevance_score: f32,
+}
+
+/// Builds the pointwise relevance prompt sent to the generation model
+pub fn relevance_prompt(query: &str, content: &str) -> String {
+    format!(
+        "Passage:\n{}\n\nQuestion: {}\n\nOn a scale from 0 to 10, how relevant is the passage to answering the question? Respond with a single number only:",
+        content,
+        query
+    )
+}
+
+/// Extracts the first numeric score from a model reply, clamped to 0..=10
+pub fn parse_relevance_score(reply: &str) -> Option<f32> {
+    let start = reply.find(|c: char| c.is_ascii_digit())?;
+    let number: String = reply[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    number
+        .trim_end_matches('.')
+        .parse::<f32>()
+        .ok()
+        .map(|score| score.clamp(0.0, 10.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_relevance_score() {
+        assert_eq!(parse_relevance_score("7"), Some(7.0));
+        assert_eq!(parse_relevance_score("Score: 8.5/10"), Some(8.5));
+        assert_eq!(parse_relevance_score("9."), Some(9.0));
+        assert_eq!(parse_relevance_score("42"), Some(10.0));
+        assert_eq!(parse_relevance_score("not relevant"), None);
+    }
+}
@@ -0,0 +1,159 @@
+use rand::Rng;
+use reqwest::StatusCode;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{ Duration, Instant };
+
+// Non-2xx response, with the error message reported by the server
+#[derive(Debug)]
+pub struct StatusError {
+    pub status: StatusCode,
+    pub message: String,
+    // `Retry-After` sent with 429/503 responses
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.status, self.message)
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+/// Whether a failed request is worth retrying: connection errors, timeouts, 429 and 5xx responses
+pub fn is_retryable(error: &anyhow::Error) -> bool {
+    if let Some(error) = error.downcast_ref::<StatusError>() {
+        return error.status == StatusCode::TOO_MANY_REQUESTS || error.status.is_server_error();
+    }
+    if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        return error.is_connect() || error.is_timeout() || error.is_request() || error.is_body();
+    }
+
+    false
+}
+
+// Exponential backoff with full jitter
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Upper bound of the delay before retry `attempt` (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Random delay between zero and the backoff, a server asked delay wins when longer
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let backoff = self.backoff(attempt);
+        let jittered = Duration::from_millis(rand::rng().random_range(0..=backoff.as_millis() as u64));
+
+        retry_after.map(|retry_after| retry_after.min(self.max_delay).max(jittered)).unwrap_or(jittered)
+    }
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+// Fails fast after `threshold` consecutive failures, letting a trial call through once `cooldown` elapsed
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker { threshold, cooldown, state: Mutex::new(BreakerState::default()) }
+    }
+
+    /// Time left before calls are let through again, `None` when the circuit is closed or half open
+    pub fn open_for(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        state.open_until.and_then(|open_until| open_until.checked_duration_since(Instant::now()))
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.failures = 0;
+        state.open_until = None;
+    }
+
+    /// Counts a failure, returns true when it opened the circuit
+    pub fn record_failure(&self) -> bool {
+        if self.threshold == 0 {
+            return false;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.failures += 1;
+        if state.failures >= self.threshold {
+            state.open_until = Some(Instant::now() + self.cooldown);
+            return true;
+        }
+
+        false
+    }
+
+    /// Opens the circuit right away, e.g. when a health check failed
+    pub fn trip(&self) {
+        if self.threshold == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.failures = self.threshold;
+        state.open_until = Some(Instant::now() + self.cooldown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy { max_retries: 5, base_delay: Duration::from_millis(100), max_delay: Duration::from_secs(1) };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(10), Duration::from_secs(1));
+        for attempt in 1..=5 {
+            assert!(policy.delay(attempt, None) <= policy.backoff(attempt));
+        }
+        assert_eq!(policy.delay(1, Some(Duration::from_millis(500))), Duration::from_millis(500));
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(60))), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let status = |status| anyhow::Error::new(StatusError { status, message: String::new(), retry_after: None });
+        assert!(is_retryable(&status(StatusCode::SERVICE_UNAVAILABLE)));
+        assert!(is_retryable(&status(StatusCode::TOO_MANY_REQUESTS)));
+        assert!(!is_retryable(&status(StatusCode::NOT_FOUND)));
+        assert!(!is_retryable(&anyhow::anyhow!("missing field `embedding`")));
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+        assert!(!breaker.record_failure());
+        assert!(breaker.open_for().is_none());
+        assert!(breaker.record_failure());
+        assert!(breaker.open_for().is_some());
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.open_for().is_none());
+        breaker.record_success();
+        assert!(!breaker.record_failure());
+
+        breaker.trip();
+        assert!(breaker.open_for().is_some());
+    }
+}
@@ -1,21 +1,331 @@
 use serde::{ Deserialize, Serialize };
+use serde_json::{ Map, Value };
+use std::collections::BTreeMap;
 use surrealdb::sql::Thing;
 
+use super::filter::Filter;
+use crate::{ RAG_LIST_PAGE_SIZE, RAG_OLLAMA_DEFAULT_NUM_CTX };
+
 // Data structures for Ollama API
 #[derive(Debug, Deserialize)]
 pub struct OllamaEmbeddingResponse {
     pub embedding: Vec<f32>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct OllamaEmbedResponse {
+    pub embeddings: Vec<Vec<f32>>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct OllamaGenerationResponse {
     pub response: String,
+    #[serde(default)]
+    pub done: bool,
+    #[serde(default)]
+    pub prompt_eval_count: usize,
+    #[serde(default)]
+    pub eval_count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaVersionResponse {
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaTagsResponse {
+    pub models: Vec<OllamaModel>,
+}
+
+// Locally available Ollama model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub modified_at: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OllamaModelDetails {
+    #[serde(default)]
+    pub format: String,
+    #[serde(default)]
+    pub family: String,
+    #[serde(default)]
+    pub parameter_size: String,
+    #[serde(default)]
+    pub quantization_level: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OllamaShowResponse {
+    #[serde(default)]
+    pub details: OllamaModelDetails,
+    // Architecture metadata, e.g. `llama.context_length`
+    #[serde(default)]
+    pub model_info: Map<String, Value>,
+    // Modelfile parameters, one `name value` per line
+    #[serde(default)]
+    pub parameters: String,
+}
+
+// Progress line of a streamed model pull
+#[derive(Debug, Clone, Deserialize)]
+pub struct OllamaPullProgress {
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub total: Option<u64>,
+    #[serde(default)]
+    pub completed: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+// Model details relevant to prompt budgeting
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    pub name: String,
+    pub family: String,
+    pub parameter_size: String,
+    pub quantization_level: String,
+    // Context length the model was trained with
+    pub context_length: Option<usize>,
+    // `num_ctx` set in the Modelfile, the window Ollama actually runs the model with
+    pub num_ctx: Option<usize>,
+}
+
+impl ModelInfo {
+    pub fn new(name: &str, show: OllamaShowResponse) -> Self {
+        let context_length = show.model_info
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+            .map(|length| length as usize);
+        let num_ctx = show.parameters
+            .lines()
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .find(|(name, _)| *name == "num_ctx")
+            .and_then(|(_, value)| value.trim().parse().ok());
+
+        ModelInfo {
+            name: name.to_string(),
+            family: show.details.family,
+            parameter_size: show.details.parameter_size,
+            quantization_level: show.details.quantization_level,
+            context_length,
+            num_ctx,
+        }
+    }
+
+    /// Tokens available to a prompt and its answer, the Modelfile `num_ctx` or Ollama's default window capped by the trained length
+    pub fn context_window(&self) -> usize {
+        self.num_ctx.unwrap_or_else(|| self.context_length.unwrap_or(RAG_OLLAMA_DEFAULT_NUM_CTX).min(RAG_OLLAMA_DEFAULT_NUM_CTX))
+    }
+}
+
+// Error body of Ollama (`{"error": "..."}`) and OpenAI-compatible servers (`{"error": {"message": "..."}}`)
+#[derive(Debug, Deserialize)]
+pub struct OllamaErrorResponse {
+    pub error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ErrorBody {
+    Message(String),
+    Object {
+        message: String,
+    },
+}
+
+impl ErrorBody {
+    pub fn message(self) -> String {
+        match self {
+            ErrorBody::Message(message) | ErrorBody::Object { message } => message,
+        }
+    }
+}
+
+// Data structures for OpenAI-compatible APIs (llama.cpp server, vLLM, LM Studio)
+#[derive(Debug, Deserialize)]
+pub struct OpenAiEmbeddingResponse {
+    pub data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiEmbedding {
+    #[serde(default)]
+    pub index: usize,
+    pub embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiModelsResponse {
+    pub data: Vec<OpenAiModel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiModel {
+    pub id: String,
+}
+
+// Chat completion, or one chunk of a streamed one
+#[derive(Debug, Deserialize)]
+pub struct OpenAiChatResponse {
+    #[serde(default)]
+    pub choices: Vec<OpenAiChoice>,
+    // Sent with the last chunk of a stream when `stream_options.include_usage` is set
+    #[serde(default)]
+    pub usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAiChoice {
+    #[serde(default)]
+    pub message: Option<OpenAiMessage>,
+    #[serde(default)]
+    pub delta: Option<OpenAiMessage>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OpenAiMessage {
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+pub struct OpenAiUsage {
+    #[serde(default)]
+    pub prompt_tokens: usize,
+    #[serde(default)]
+    pub completion_tokens: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentMetaData {
     pub index: usize,
     pub source: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
+    // Access control, documents without owner and groups are public
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+impl DocumentMetaData {
+    pub fn new(index: usize, source: &str, user_metadata: &UserMetaData) -> Self {
+        DocumentMetaData {
+            index,
+            source: source.to_string(),
+            tags: user_metadata.tags.clone(),
+            attributes: user_metadata.attributes.clone(),
+            owner: user_metadata.owner.clone(),
+            groups: user_metadata.groups.clone(),
+        }
+    }
+}
+
+// User-defined tags and key/values, set at ingest (CLI flags or `.meta.json` sidecar files)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserMetaData {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+impl UserMetaData {
+    // Merge another set of user metadata, values from `other` win
+    pub fn merge(&self, other: &UserMetaData) -> UserMetaData {
+        let mut merged = self.clone();
+        for tag in &other.tags {
+            if !merged.tags.contains(tag) {
+                merged.tags.push(tag.clone());
+            }
+        }
+        merged.attributes.extend(other.attributes.clone());
+        if other.owner.is_some() {
+            merged.owner = other.owner.clone();
+        }
+        for group in &other.groups {
+            if !merged.groups.contains(group) {
+                merged.groups.push(group.clone());
+            }
+        }
+        merged
+    }
+}
+
+// Changes applied to the user metadata of existing documents
+#[derive(Debug, Clone, Default)]
+pub struct MetadataUpdate {
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+    pub set_attributes: Map<String, Value>,
+    pub unset_attributes: Vec<String>,
+    pub add_groups: Vec<String>,
+    pub remove_groups: Vec<String>,
+    // `Some(None)` clears the owner
+    pub owner: Option<Option<String>>,
+}
+
+// Selects existing documents by record id or by source file
+#[derive(Debug, Clone)]
+pub enum DocumentTarget {
+    Id(String),
+    Source(String),
+}
+
+// Source file with the number of chunks stored for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceSummary {
+    pub source: String,
+    pub chunks: usize,
+}
+
+// Per-source chunk statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceStats {
+    pub source: String,
+    pub chunks: usize,
+    pub characters: usize,
+}
+
+// Knowledge base statistics of a collection, used for capacity planning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeBaseStats {
+    pub collection: String,
+    pub table: String,
+    pub embedding_model: String,
+    pub dimension: u16,
+    pub index: Option<String>,
+    pub chunks: usize,
+    pub total_characters: usize,
+    pub min_chunk_length: usize,
+    pub max_chunk_length: usize,
+    pub avg_chunk_length: f64,
+    pub first_created_at: Option<String>,
+    pub last_created_at: Option<String>,
+    // Storage estimates in bytes, SurrealDB stores floats as 64-bit and the vector index keeps its own copy
+    pub estimated_content_bytes: u64,
+    pub estimated_embedding_bytes: u64,
+    pub estimated_index_bytes: u64,
+    pub estimated_total_bytes: u64,
+    pub sources: Vec<SourceStats>,
 }
 
 // Document structure for our RAG system
@@ -24,6 +334,187 @@ pub struct Document {
     pub id: Thing,
     pub content: String,
     pub metadata: DocumentMetaData,
+    #[serde(default)]
+    pub embedding: Vec<f32>,
+    pub created_at: String,
+}
+
+// Named knowledge base collection, each stored in its own table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub name: String,
+    pub table: String,
+    pub embedding_model: String,
+    pub dimension: u16,
+    pub created_at: String,
+    // Table a running re-embed copies into, writes are refused until it is swapped in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reembedding: Option<String>,
+}
+
+// Tenant mapped to its own SurrealDB namespace/database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tenant {
+    pub name: String,
+    pub namespace: String,
+    pub database: String,
+    pub created_at: String,
+}
+
+// JSON Lines export format, a header line followed by one line per chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ExportRecord {
+    Header(ExportHeader),
+    Chunk(ExportChunk),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportHeader {
+    pub format_version: u32,
+    pub collection: String,
+    pub embedding_model: String,
+    pub dimension: u16,
+    pub schema_version: u32,
+    pub exported_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportChunk {
+    pub id: String,
+    pub content: String,
+    pub metadata: DocumentMetaData,
     pub embedding: Vec<f32>,
     pub created_at: String,
 }
+
+// Backup manifest written next to the SurrealDB export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub created_at: String,
+    pub namespace: String,
+    pub database: String,
+    pub schema_version: u32,
+    pub embedding_model: String,
+    pub dimension: u16,
+    pub collections: Vec<Collection>,
+    pub counts: BTreeMap<String, usize>,
+}
+
+// Ordering of document listings
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DocumentOrder {
+    // By source, then chunk index
+    #[default]
+    Source,
+    CreatedAt,
+    Id,
+}
+
+// Pagination and projection of document listings
+#[derive(Debug, Clone)]
+pub struct ListOptions {
+    pub start: usize,
+    pub limit: usize,
+    pub order: DocumentOrder,
+    // Embedding vectors are omitted unless requested
+    pub with_embedding: bool,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        ListOptions {
+            start: 0,
+            limit: RAG_LIST_PAGE_SIZE,
+            order: DocumentOrder::default(),
+            with_embedding: false,
+        }
+    }
+}
+
+// Tenant and collection a RAG system is opened on, the configured database and default collection when unset
+#[derive(Debug, Clone)]
+pub struct Scope {
+    pub tenant: Option<String>,
+    pub collection: Option<String>,
+    // Verify the collection's embedding model, off for commands that never embed
+    pub verify: bool,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Scope { tenant: None, collection: None, verify: true }
+    }
+}
+
+// Options for the complete RAG pipeline
+#[derive(Debug, Clone, Default)]
+pub struct QueryOptions {
+    pub filter: Option<Filter>,
+}
+
+// Retrieved chunk an answer is based on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceReference {
+    pub id: String,
+    pub source: String,
+    pub index: usize,
+}
+
+impl From<&Document> for SourceReference {
+    fn from(doc: &Document) -> Self {
+        SourceReference {
+            id: doc.id.id.to_raw(),
+            source: doc.metadata.source.clone(),
+            index: doc.metadata.index,
+        }
+    }
+}
+
+// Answer of the complete RAG pipeline with its sources and token usage
+#[derive(Debug, Clone, Serialize)]
+pub struct RagAnswer {
+    pub answer: String,
+    pub sources: Vec<SourceReference>,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+// Completed generation of any backend with its token usage
+#[derive(Debug, Clone, Default)]
+pub struct Generation {
+    pub text: String,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+impl From<OllamaGenerationResponse> for Generation {
+    fn from(generation: OllamaGenerationResponse) -> Self {
+        Generation {
+            text: generation.response,
+            prompt_tokens: generation.prompt_eval_count,
+            completion_tokens: generation.eval_count,
+        }
+    }
+}
+
+// Piece of a streamed generation, token counts are set on the final (`done`) piece
+#[derive(Debug, Clone, Default)]
+pub struct GenerationChunk {
+    pub text: String,
+    pub done: bool,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+impl From<OllamaGenerationResponse> for GenerationChunk {
+    fn from(generation: OllamaGenerationResponse) -> Self {
+        GenerationChunk {
+            text: generation.response,
+            done: generation.done,
+            prompt_tokens: generation.prompt_eval_count,
+            completion_tokens: generation.eval_count,
+        }
+    }
+}
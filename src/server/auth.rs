@@ -0,0 +1,156 @@
+use axum::extract::{ Request, State };
+use axum::http::{ header, HeaderMap, Method, StatusCode };
+use axum::middleware::Next;
+use axum::response::Response;
+use std::sync::Arc;
+
+use crate::{ ApiKey, ApiScope, Identity, UserMetaData };
+use super::{ ApiError, AppState };
+
+// Authenticated caller of a request, no key when authentication is disabled
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub key: Option<ApiKey>,
+    // Tenant the request is served from, bound to the key or, without authentication, the `X-Tenant` header
+    pub tenant: Option<String>,
+}
+
+impl Caller {
+    /// Identity documents are restricted to, named after the key with its groups
+    pub fn identity(&self) -> Option<Identity> {
+        self.key.as_ref().map(|key| Identity { user: key.name.clone(), groups: key.groups.clone() })
+    }
+
+    /// Checks the owner and groups of ingested documents against the caller's identity,
+    /// documents without either are owned by the caller since keys cannot publish for everyone
+    pub fn authorize_access(&self, user_metadata: &mut UserMetaData) -> Result<(), ApiError> {
+        let Some(identity) = self.identity() else {
+            return Ok(());
+        };
+        if let Some(owner) = user_metadata.owner.as_ref().filter(|owner| **owner != identity.user) {
+            return Err(ApiError::new(StatusCode::FORBIDDEN, format!("API key '{}' cannot ingest documents owned by '{}'", identity.user, owner)));
+        }
+        if let Some(group) = user_metadata.groups.iter().find(|group| !identity.groups.contains(group)) {
+            return Err(ApiError::new(StatusCode::FORBIDDEN, format!("API key '{}' is not a member of group '{}'", identity.user, group)));
+        }
+        if user_metadata.owner.is_none() && user_metadata.groups.is_empty() {
+            user_metadata.owner = Some(identity.user);
+        }
+
+        Ok(())
+    }
+}
+
+/// Scope needed for a route, ingesting and deleting documents needs `ingest`, everything else `query`
+fn required_scope(method: &Method, path: &str) -> ApiScope {
+    if method == Method::DELETE || (method == Method::POST && path == "/documents") { ApiScope::Ingest } else { ApiScope::Query }
+}
+
+/// API key from `Authorization: Bearer <key>` or `X-API-Key: <key>`
+fn api_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| headers.get("x-api-key").and_then(|value| value.to_str().ok()))
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+// Enforces API keys, scopes and daily request quotas on every route but `/health`
+pub async fn authenticate(State(state): State<Arc<AppState>>, mut request: Request, next: Next) -> Result<Response, ApiError> {
+    if !state.auth || request.uri().path() == "/health" {
+        let tenant = request
+            .headers()
+            .get("x-tenant")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+            .filter(|_| !state.auth);
+        request.extensions_mut().insert(Caller { key: None, tenant });
+        return Ok(next.run(request).await);
+    }
+
+    let secret = api_key(request.headers()).ok_or_else(||
+        ApiError::new(StatusCode::UNAUTHORIZED, "Missing API key, send `Authorization: Bearer <key>`")
+    )?;
+    let key = state.rag
+        .authenticate_api_key(secret).await?
+        .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "Invalid or revoked API key"))?;
+
+    let scope = required_scope(request.method(), request.uri().path());
+    if !key.has_scope(scope) {
+        return Err(ApiError::new(StatusCode::FORBIDDEN, format!("API key '{}' lacks the '{}' scope", key.name, scope)));
+    }
+    if state.rag.claim_api_key_request(&key.name).await?.is_none() {
+        let quota = key.exceeded_quota().unwrap_or("requests");
+        return Err(ApiError::new(StatusCode::TOO_MANY_REQUESTS, format!("Daily {} quota of API key '{}' exceeded", quota, key.name)));
+    }
+
+    let tenant = key.tenant.clone();
+    request.extensions_mut().insert(Caller { key: Some(key), tenant });
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(required_scope(&Method::POST, "/query"), ApiScope::Query);
+        assert_eq!(required_scope(&Method::POST, "/v1/chat/completions"), ApiScope::Query);
+        assert_eq!(required_scope(&Method::GET, "/documents/5f0c"), ApiScope::Query);
+        assert_eq!(required_scope(&Method::POST, "/documents"), ApiScope::Ingest);
+        assert_eq!(required_scope(&Method::DELETE, "/documents/5f0c"), ApiScope::Ingest);
+    }
+
+    #[test]
+    fn test_api_key() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(api_key(&headers), None);
+        headers.insert("x-api-key", HeaderValue::from_static("rr_1"));
+        assert_eq!(api_key(&headers), Some("rr_1"));
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer rr_2"));
+        assert_eq!(api_key(&headers), Some("rr_2"));
+    }
+
+    #[test]
+    fn test_authorize_access() {
+        let key = ApiKey {
+            name: "frontend".to_string(),
+            key_hash: String::new(),
+            prefix: String::new(),
+            scopes: vec![ApiScope::Ingest],
+            groups: vec!["engineering".to_string()],
+            tenant: None,
+            requests_per_day: None,
+            tokens_per_day: None,
+            usage_day: None,
+            requests: 0,
+            tokens: 0,
+            created_at: String::new(),
+            revoked_at: None,
+        };
+        let caller = Caller { key: Some(key), tenant: None };
+
+        // Documents without owner and groups are owned by the key instead of public
+        let mut metadata = UserMetaData::default();
+        caller.authorize_access(&mut metadata).unwrap();
+        assert_eq!(metadata.owner.as_deref(), Some("frontend"));
+
+        let mut metadata = UserMetaData { groups: vec!["engineering".to_string()], ..Default::default() };
+        caller.authorize_access(&mut metadata).unwrap();
+        assert_eq!(metadata.owner, None);
+
+        let mut metadata = UserMetaData { owner: Some("backend".to_string()), ..Default::default() };
+        assert_eq!(caller.authorize_access(&mut metadata).unwrap_err().status, StatusCode::FORBIDDEN);
+        let mut metadata = UserMetaData { groups: vec!["finance".to_string()], ..Default::default() };
+        assert_eq!(caller.authorize_access(&mut metadata).unwrap_err().status, StatusCode::FORBIDDEN);
+
+        // Without authentication the metadata is taken as sent
+        let mut metadata = UserMetaData { owner: Some("backend".to_string()), ..Default::default() };
+        Caller { key: None, tenant: None }.authorize_access(&mut metadata).unwrap();
+        assert_eq!(metadata.owner.as_deref(), Some("backend"));
+    }
+}
@@ -0,0 +1,69 @@
+use axum::http::StatusCode;
+use axum::response::{ IntoResponse, Response };
+use axum::Json;
+use serde_json::json;
+use std::fmt::Display;
+use tracing::error;
+
+// Error returned by HTTP handlers, rendered as `{"error": "..."}`. Server errors are logged
+// with their detail and rendered with the generic reason only, not to leak internals to clients
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Display) -> Self {
+        ApiError { status, message: message.to_string() }
+    }
+
+    pub fn bad_request(message: impl Display) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn not_found(message: impl Display) -> Self {
+        Self::new(StatusCode::NOT_FOUND, message)
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let message = if self.status.is_server_error() {
+            error!("{}", self.message);
+            self.status.canonical_reason().unwrap_or("server error").to_lowercase()
+        } else {
+            self.message
+        };
+        (self.status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    async fn body(error: ApiError) -> (StatusCode, String) {
+        let response = error.into_response();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, String::from_utf8(bytes.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_hide_detail() {
+        let error = ApiError::from(anyhow!("password=secret").context("connecting to ws://db:8000"));
+        assert_eq!(error.message, "connecting to ws://db:8000: password=secret");
+        assert_eq!(body(error).await, (StatusCode::INTERNAL_SERVER_ERROR, r#"{"error":"internal server error"}"#.to_string()));
+
+        let error = ApiError::bad_request("Empty question");
+        assert_eq!(body(error).await, (StatusCode::BAD_REQUEST, r#"{"error":"Empty question"}"#.to_string()));
+    }
+}
@@ -0,0 +1,212 @@
+use axum::body::Bytes;
+use axum::extract::{ Extension, FromRequest, Multipart, Path, Query, Request, State };
+use axum::http::{ header, StatusCode };
+use axum::Json;
+use serde_json::Value;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    chunk_text,
+    load_pdf,
+    record_key,
+    sanitize_chunk_comprehensive,
+    DocumentMetaData,
+    DocumentTarget,
+    Filter,
+    KnowledgeBaseStats,
+    QueryOptions,
+    UserMetaData,
+    RAG_CHUNK_SIZE,
+};
+use super::structures::{
+    CollectionParams,
+    DeleteResponse,
+    DocumentResponse,
+    HealthResponse,
+    IngestRequest,
+    IngestResponse,
+    QueryRequest,
+    QueryResponse,
+};
+use super::{ ApiError, AppState, Caller };
+
+/// Splits a file upload into sanitized chunks, PDFs are parsed through a temporary file off the async runtime
+async fn load_upload(file_name: &str, bytes: Bytes) -> Result<Vec<String>, ApiError> {
+    let chunks = if file_name.to_lowercase().ends_with(".pdf") {
+        let path = std::env::temp_dir().join(format!("rigrag-{}.pdf", Uuid::new_v4()));
+        let chunks = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+            std::fs::write(&path, &bytes)?;
+            let chunks = load_pdf(path.clone());
+            let _ = std::fs::remove_file(&path);
+            Ok(chunks)
+        }).await.map_err(anyhow::Error::from)??;
+        chunks.map_err(ApiError::bad_request)?
+    } else {
+        let text = std::str::from_utf8(&bytes).map_err(|_| ApiError::bad_request(format!("'{}' is neither a PDF nor UTF-8 text", file_name)))?;
+        chunk_text(text, RAG_CHUNK_SIZE)
+    };
+
+    Ok(chunks.iter().map(|chunk| sanitize_chunk_comprehensive(chunk)).collect())
+}
+
+pub async fn health(State(state): State<Arc<AppState>>) -> Result<Json<HealthResponse>, ApiError> {
+    state.rag.health().await.map_err(|e| ApiError::new(StatusCode::SERVICE_UNAVAILABLE, e))?;
+
+    Ok(Json(HealthResponse { status: "ok".to_string() }))
+}
+
+pub async fn stats(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Query(params): Query<CollectionParams>
+) -> Result<Json<KnowledgeBaseStats>, ApiError> {
+    let rag = state.rag(&caller, params.collection.as_deref()).await?;
+
+    Ok(Json(rag.stats().await?))
+}
+
+pub async fn query(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Json(request): Json<QueryRequest>
+) -> Result<Json<QueryResponse>, ApiError> {
+    if request.question.trim().is_empty() {
+        return Err(ApiError::bad_request("Question must not be empty"));
+    }
+    let rag = state.rag(&caller, request.collection.as_deref()).await?;
+    let options = QueryOptions { filter: Filter::from_value(request.filter).map_err(ApiError::bad_request)? };
+    let answer = rag.query_with_sources(&request.question, &options).await?;
+    state.record_tokens(&caller, answer.prompt_tokens + answer.completion_tokens).await;
+
+    Ok(Json(QueryResponse { answer: answer.answer, sources: answer.sources }))
+}
+
+// Ingests a JSON `{text, source}` body, or a multipart form with `file` parts and optional
+// `tag`, `meta` (key=value), `owner`, `group` and `collection` fields. API keys may only set
+// themselves as owner and their own groups, documents without either are owned by the key
+pub async fn ingest(State(state): State<Arc<AppState>>, Extension(caller): Extension<Caller>, request: Request) -> Result<(StatusCode, Json<IngestResponse>), ApiError> {
+    let is_multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+
+    let (collection, mut user_metadata, sources) = if is_multipart {
+        let mut multipart = Multipart::from_request(request, &()).await.map_err(ApiError::bad_request)?;
+        let mut collection = None;
+        let mut user_metadata = UserMetaData::default();
+        let mut files = Vec::new();
+        while let Some(field) = multipart.next_field().await.map_err(ApiError::bad_request)? {
+            let name = field.name().unwrap_or_default().to_string();
+            match (name.as_str(), field.file_name().map(str::to_string)) {
+                ("file", Some(file_name)) => {
+                    let bytes = field.bytes().await.map_err(ApiError::bad_request)?;
+                    files.push((file_name, bytes));
+                }
+                ("tag", None) => user_metadata.tags.push(field.text().await.map_err(ApiError::bad_request)?),
+                ("group", None) => user_metadata.groups.push(field.text().await.map_err(ApiError::bad_request)?),
+                ("owner", None) => {
+                    user_metadata.owner = Some(field.text().await.map_err(ApiError::bad_request)?);
+                }
+                ("meta", None) => {
+                    let pair = field.text().await.map_err(ApiError::bad_request)?;
+                    match pair.split_once('=') {
+                        Some((key, value)) if !key.trim().is_empty() => {
+                            user_metadata.attributes.insert(key.trim().to_string(), Value::String(value.trim().to_string()));
+                        }
+                        _ => {
+                            return Err(ApiError::bad_request(format!("Invalid metadata '{}', expected key=value", pair)));
+                        }
+                    }
+                }
+                ("collection", None) => {
+                    collection = Some(field.text().await.map_err(ApiError::bad_request)?);
+                }
+                _ => {
+                    return Err(ApiError::bad_request(format!("Unexpected form field '{}'", name)));
+                }
+            }
+        }
+        if files.is_empty() {
+            return Err(ApiError::bad_request("No `file` part in upload"));
+        }
+
+        let mut sources = Vec::new();
+        for (file_name, bytes) in files {
+            let chunks = load_upload(&file_name, bytes).await?;
+            sources.push((file_name, chunks));
+        }
+        (collection, user_metadata, sources)
+    } else {
+        let Json(request) = Json::<IngestRequest>::from_request(request, &()).await.map_err(ApiError::bad_request)?;
+        if request.source.trim().is_empty() {
+            return Err(ApiError::bad_request("Source must not be empty"));
+        }
+        let chunks = chunk_text(&request.text, RAG_CHUNK_SIZE)
+            .iter()
+            .map(|chunk| sanitize_chunk_comprehensive(chunk))
+            .collect();
+        let user_metadata = UserMetaData { tags: request.tags, attributes: request.attributes, owner: request.owner, groups: request.groups };
+        (request.collection, user_metadata, vec![(request.source, chunks)])
+    };
+
+    caller.authorize_access(&mut user_metadata)?;
+    let rag = state.rag(&caller, collection.as_deref()).await?;
+    let mut documents = Vec::new();
+    for (source, chunks) in sources {
+        if chunks.is_empty() {
+            return Err(ApiError::bad_request(format!("No content found in '{}'", source)));
+        }
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            documents.push((chunk, DocumentMetaData::new(i, &source, &user_metadata)));
+        }
+    }
+
+    let chunks = documents.len();
+    let ids = rag
+        .store_documents(documents).await?
+        .iter()
+        .map(|id| record_key(id).to_string())
+        .collect();
+    info!("Ingested {} chunks into collection '{}'", chunks, rag.collection);
+
+    Ok((StatusCode::CREATED, Json(IngestResponse { ids, chunks })))
+}
+
+pub async fn get_document(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    Query(params): Query<CollectionParams>
+) -> Result<Json<DocumentResponse>, ApiError> {
+    let rag = state.rag(&caller, params.collection.as_deref()).await?;
+    let doc = rag
+        .get_document(record_key(&id)).await?
+        .ok_or_else(|| ApiError::not_found(format!("Document '{}' not found", id)))?;
+
+    Ok(
+        Json(DocumentResponse {
+            id: doc.id.id.to_raw(),
+            content: doc.content,
+            metadata: doc.metadata,
+            created_at: doc.created_at,
+        })
+    )
+}
+
+pub async fn delete_document(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    Query(params): Query<CollectionParams>
+) -> Result<Json<DeleteResponse>, ApiError> {
+    let rag = state.rag(&caller, params.collection.as_deref()).await?;
+    let deleted = rag.delete_documents(&DocumentTarget::Id(record_key(&id).to_string())).await?;
+    if deleted == 0 {
+        return Err(ApiError::not_found(format!("Document '{}' not found", id)));
+    }
+
+    Ok(Json(DeleteResponse { deleted }))
+}
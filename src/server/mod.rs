@@ -0,0 +1,130 @@
+mod auth;
+mod error;
+mod handlers;
+mod openai;
+mod structures;
+
+use anyhow::Result;
+use axum::extract::DefaultBodyLimit;
+use axum::middleware;
+use axum::routing::{ get, post };
+use axum::Router;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{ info, warn };
+
+use crate::{ RagSystem, RAG_DEFAULT_COLLECTION, RAG_UPLOAD_LIMIT };
+
+pub use auth::Caller;
+pub use error::ApiError;
+
+// State shared by all HTTP handlers
+pub struct AppState {
+    rag: RagSystem,
+    // Require API keys on every route but `/health`
+    auth: bool,
+    // Connections of the tenants requested so far, one session per tenant namespace/database
+    tenants: Mutex<HashMap<String, RagSystem>>,
+    // RAG systems scoped to the (tenant, collection) pairs requested so far
+    collections: Mutex<HashMap<(Option<String>, String), RagSystem>>,
+}
+
+impl AppState {
+    pub fn new(rag: RagSystem, auth: bool) -> Self {
+        AppState { rag, auth, tenants: Mutex::new(HashMap::new()), collections: Mutex::new(HashMap::new()) }
+    }
+
+    /// Adds generated tokens to the daily usage of the caller's API key
+    pub async fn record_tokens(&self, caller: &Caller, tokens: usize) {
+        if let Some(key) = &caller.key {
+            if let Err(e) = self.rag.record_api_key_usage(&key.name, 0, tokens).await {
+                warn!("Failed to record token usage of API key '{}': {}", key.name, e);
+            }
+        }
+    }
+
+    /// RAG system for the requested collection, restricted to what the caller may see. The cached system is
+    /// refreshed from the registry, a collection re-embedded meanwhile lives in another table
+    pub async fn rag(&self, caller: &Caller, collection: Option<&str>) -> Result<RagSystem, ApiError> {
+        Ok(self.collection(caller.tenant.as_deref(), collection).await?.refresh().await?.with_identity(caller.identity()))
+    }
+
+    /// RAG system of a tenant, the configured database when none is given
+    async fn tenant(&self, tenant: Option<&str>) -> Result<RagSystem, ApiError> {
+        let Some(name) = tenant else {
+            return Ok(self.rag.clone());
+        };
+
+        if let Some(rag) = self.tenants.lock().await.get(name) {
+            return Ok(rag.clone());
+        }
+        // Connect without holding the lock, requests for other tenants must not wait on it. Concurrent
+        // first requests may both connect, the first one cached wins
+        let rag = self.rag.with_tenant(name).await.map_err(ApiError::bad_request)?;
+
+        Ok(self.tenants.lock().await.entry(name.to_string()).or_insert(rag).clone())
+    }
+
+    /// RAG system for the requested collection of a tenant, the default collection when none is given
+    async fn collection(&self, tenant: Option<&str>, collection: Option<&str>) -> Result<RagSystem, ApiError> {
+        let rag = self.tenant(tenant).await?;
+        let name = match collection {
+            Some(name) if name != rag.collection => name,
+            _ => {
+                return Ok(rag);
+            }
+        };
+
+        let key = (tenant.map(str::to_string), name.to_string());
+        if let Some(rag) = self.collections.lock().await.get(&key) {
+            return Ok(rag.clone());
+        }
+        // Resolved without holding the lock, as tenants are
+        let rag = rag.with_collection(name).await.map_err(ApiError::bad_request)?;
+
+        Ok(self.collections.lock().await.entry(key).or_insert(rag).clone())
+    }
+
+    /// RAG system for an OpenAI `model` name, collections are exposed as models and other names fall back to the default collection
+    pub async fn rag_for_model(&self, caller: &Caller, model: Option<&str>) -> Result<RagSystem, ApiError> {
+        let Some(model) = model else {
+            return self.rag(caller, None).await;
+        };
+        let cached = self.collections.lock().await.contains_key(&(caller.tenant.clone(), model.to_string()));
+        if cached || model == RAG_DEFAULT_COLLECTION || self.tenant(caller.tenant.as_deref()).await?.get_collection(model).await?.is_some() {
+            return self.rag(caller, Some(model)).await;
+        }
+
+        self.rag(caller, None).await
+    }
+}
+
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/health", get(handlers::health))
+        .route("/stats", get(handlers::stats))
+        .route("/query", post(handlers::query))
+        .route("/documents", post(handlers::ingest))
+        .route("/documents/{id}", get(handlers::get_document).delete(handlers::delete_document))
+        .route("/v1/models", get(openai::models))
+        .route("/v1/chat/completions", post(openai::chat_completions))
+        .route("/v1/embeddings", post(openai::embeddings))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::authenticate))
+        .layer(DefaultBodyLimit::max(RAG_UPLOAD_LIMIT))
+        .with_state(state)
+}
+
+/// Serves the HTTP API until the process is stopped
+pub async fn serve(rag: RagSystem, addr: &str, auth: bool) -> Result<()> {
+    if !auth {
+        warn!("API key authentication is disabled, every client has full access");
+    }
+    let app = router(Arc::new(AppState::new(rag, auth)));
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+
+    info!("HTTP API listening on {}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
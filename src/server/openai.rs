@@ -0,0 +1,190 @@
+use axum::extract::{ Extension, State };
+use axum::response::sse::{ Event, KeepAlive, Sse };
+use axum::response::{ IntoResponse, Response };
+use axum::Json;
+use futures::stream::{ self, StreamExt };
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{ Filter, QueryOptions };
+use super::structures::{
+    ChatChoice,
+    ChatChunkChoice,
+    ChatCompletionChunk,
+    ChatCompletionRequest,
+    ChatCompletionResponse,
+    ChatDelta,
+    ChatMessage,
+    ChatResponseMessage,
+    EmbeddingData,
+    EmbeddingInput,
+    EmbeddingRequest,
+    EmbeddingResponse,
+    ModelInfo,
+    ModelList,
+    Usage,
+};
+use super::{ ApiError, AppState, Caller };
+
+/// Text of a message content, either a string or a list of text parts
+fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) =>
+            parts
+                .iter()
+                .filter_map(|part| part.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// The question answered by the RAG pipeline, the last user message
+fn question(messages: &[ChatMessage]) -> Option<String> {
+    messages
+        .iter()
+        .rev()
+        .find(|message| message.role == "user")
+        .map(|message| message_text(&message.content))
+        .filter(|text| !text.trim().is_empty())
+}
+
+// Collections are exposed as models
+pub async fn models(State(state): State<Arc<AppState>>, Extension(caller): Extension<Caller>) -> Result<Json<ModelList>, ApiError> {
+    let rag = state.rag(&caller, None).await?;
+    let mut data = rag
+        .list_collections().await?
+        .into_iter()
+        .map(|collection| ModelInfo {
+            id: collection.name,
+            object: "model",
+            created: chrono::DateTime::parse_from_rfc3339(&collection.created_at).map(|created| created.timestamp()).unwrap_or_default(),
+            owned_by: "rigrag",
+        })
+        .collect::<Vec<_>>();
+    if !data.iter().any(|model| model.id == rag.collection) {
+        data.insert(0, ModelInfo { id: rag.collection.clone(), object: "model", created: 0, owned_by: "rigrag" });
+    }
+
+    Ok(Json(ModelList { object: "list", data }))
+}
+
+// Chat completions answered by the RAG pipeline, only the last user message is used as the question
+pub async fn chat_completions(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Json(request): Json<ChatCompletionRequest>
+) -> Result<Response, ApiError> {
+    let question = question(&request.messages).ok_or_else(|| ApiError::bad_request("No user message to answer"))?;
+    let rag = match request.collection.as_deref() {
+        Some(collection) => state.rag(&caller, Some(collection)).await?,
+        None => state.rag_for_model(&caller, request.model.as_deref()).await?,
+    };
+    let options = QueryOptions { filter: Filter::from_value(request.filter).map_err(ApiError::bad_request)? };
+    let id = format!("chatcmpl-{}", Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+    let model = rag.collection.clone();
+
+    if !request.stream {
+        let answer = rag.query_with_sources(&question, &options).await?;
+        state.record_tokens(&caller, answer.prompt_tokens + answer.completion_tokens).await;
+        return Ok(
+            Json(ChatCompletionResponse {
+                id,
+                object: "chat.completion",
+                created,
+                model,
+                choices: vec![ChatChoice {
+                    index: 0,
+                    message: ChatResponseMessage { role: "assistant", content: answer.answer },
+                    finish_reason: "stop",
+                }],
+                usage: Usage::new(answer.prompt_tokens, answer.completion_tokens),
+                sources: answer.sources,
+            }).into_response()
+        );
+    }
+
+    let (sources, generation) = rag.query_stream(&question, &options).await?;
+    let chunk = move |delta: ChatDelta, finish_reason: Option<&'static str>, usage: Option<Usage>, sources| ChatCompletionChunk {
+        id: id.clone(),
+        object: "chat.completion.chunk",
+        created,
+        model: model.clone(),
+        choices: vec![ChatChunkChoice { index: 0, delta, finish_reason }],
+        usage,
+        sources,
+    };
+
+    let head = Event::default()
+        .json_data(chunk(ChatDelta { role: Some("assistant"), content: None }, None, None, Some(sources)))
+        .map_err(anyhow::Error::from);
+    let body = generation.flat_map(move |piece| {
+        let events = match piece {
+            Ok(piece) => {
+                let mut events = Vec::new();
+                if !piece.text.is_empty() {
+                    events.push(chunk(ChatDelta { role: None, content: Some(piece.text) }, None, None, None));
+                }
+                if piece.done {
+                    let (state, caller) = (state.clone(), caller.clone());
+                    tokio::spawn(async move { state.record_tokens(&caller, piece.prompt_tokens + piece.completion_tokens).await });
+                    events.push(chunk(ChatDelta::default(), Some("stop"), Some(Usage::new(piece.prompt_tokens, piece.completion_tokens)), None));
+                }
+                events
+                    .into_iter()
+                    .map(|event| Event::default().json_data(event).map_err(anyhow::Error::from))
+                    .collect()
+            }
+            Err(e) => vec![Err(e)],
+        };
+        stream::iter(events)
+    });
+    let done = stream::once(async { Ok(Event::default().data("[DONE]")) });
+
+    Ok(Sse::new(stream::iter([head]).chain(body).chain(done)).keep_alive(KeepAlive::default()).into_response())
+}
+
+pub async fn embeddings(
+    State(state): State<Arc<AppState>>,
+    Extension(caller): Extension<Caller>,
+    Json(request): Json<EmbeddingRequest>
+) -> Result<Json<EmbeddingResponse>, ApiError> {
+    let rag = state.rag_for_model(&caller, request.model.as_deref()).await?;
+    let input = match request.input {
+        EmbeddingInput::Single(text) => vec![text],
+        EmbeddingInput::Batch(texts) => texts,
+    };
+    if input.is_empty() {
+        return Err(ApiError::bad_request("Input must not be empty"));
+    }
+
+    let data = rag
+        .generate_embeddings(&input).await?
+        .into_iter()
+        .enumerate()
+        .map(|(index, embedding)| EmbeddingData { object: "embedding", index, embedding })
+        .collect();
+
+    Ok(Json(EmbeddingResponse { object: "list", data, model: rag.embedding_model.clone(), usage: Usage::new(0, 0) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_question() {
+        let messages: Vec<ChatMessage> = serde_json::from_value(json!([
+            { "role": "system", "content": "You are helpful" },
+            { "role": "user", "content": "First question" },
+            { "role": "assistant", "content": "First answer" },
+            { "role": "user", "content": [{ "type": "text", "text": "What is" }, { "type": "text", "text": "the last question?" }] }
+        ])).unwrap();
+        assert_eq!(question(&messages).unwrap(), "What is\nthe last question?");
+        assert!(question(&messages[..1]).is_none());
+    }
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::RagSystem;
+
+pub async fn backup(rag: &RagSystem, path: &str) -> Result<()> {
+    // SurrealDB export of the configured namespace/database plus a manifest
+    let manifest = rag.backup(Path::new(path)).await?;
+    println!("Backup written to {} (manifest: {}.manifest.json)", path, path);
+    println!("Schema version: {}", manifest.schema_version);
+    for (collection, count) in &manifest.counts {
+        println!("- Collection {}: {} chunks", collection, count);
+    }
+
+    Ok(())
+}
+
+pub async fn restore(rag: &RagSystem, path: &str, force: bool) -> Result<()> {
+    let manifest = rag.restore(Path::new(path), force).await?;
+    println!("Restored backup of {}/{} created at {}", manifest.namespace, manifest.database, manifest.created_at);
+    for (collection, count) in &manifest.counts {
+        println!("- Collection {}: {} chunks", collection, count);
+    }
+
+    Ok(())
+}
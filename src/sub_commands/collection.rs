@@ -0,0 +1,36 @@
+use anyhow::Result;
+
+use crate::RagSystem;
+
+pub async fn collection_create(rag: &RagSystem, name: &str, embedding_model: &str, embedding_model_dimension: u16) -> Result<()> {
+    let collection = rag.create_collection(name, embedding_model, embedding_model_dimension).await?;
+    println!(
+        "Created collection '{}' (table: {}, embedding model: {}, dimension: {})",
+        collection.name,
+        collection.table,
+        collection.embedding_model,
+        collection.dimension
+    );
+
+    Ok(())
+}
+
+pub async fn collection_list(rag: &RagSystem) -> Result<()> {
+    let collections = rag.list_collections().await?;
+    println!("Total collections: {}", collections.len());
+
+    for collection in collections {
+        println!("- Collection: {}", collection.name);
+        println!("  Table: {}", collection.table);
+        println!("  Embedding model: {} ({} dimensions)", collection.embedding_model, collection.dimension);
+        println!("  Created at: {}", collection.created_at);
+    }
+    Ok(())
+}
+
+pub async fn collection_drop(rag: &RagSystem, name: &str) -> Result<()> {
+    rag.drop_collection(name).await?;
+    println!("Dropped collection '{}'", name);
+
+    Ok(())
+}
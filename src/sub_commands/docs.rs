@@ -0,0 +1,50 @@
+use anyhow::{ bail, Result };
+
+use crate::{ DocumentTarget, RagSystem };
+
+pub async fn docs_list(rag: &RagSystem, page: usize, page_size: usize) -> Result<()> {
+    let total = rag.count_sources().await?;
+    let pages = total.div_ceil(page_size.max(1)).max(1);
+    let sources = rag.list_sources((page.max(1) - 1) * page_size, page_size).await?;
+
+    println!("\n{}", "=".repeat(60));
+    println!("Sources in collection '{}': {} (page {} of {})", rag.collection, total, page.max(1), pages);
+    for source in sources {
+        println!("- {} ({} chunks)", source.source, source.chunks);
+    }
+    Ok(())
+}
+
+pub async fn docs_show(rag: &RagSystem, id_or_source: &str) -> Result<()> {
+    if let Some(doc) = rag.get_document(id_or_source).await? {
+        println!("\n{}", "=".repeat(60));
+        println!("Document ID: {}", doc.id.id);
+        println!("Source: {} (chunk {})", doc.metadata.source, doc.metadata.index);
+        println!("Tags: {:?}", doc.metadata.tags);
+        println!("Attributes: {}", serde_json::Value::Object(doc.metadata.attributes));
+        println!("Created at: {}", doc.created_at);
+        println!("Embedding dimensions: {}", doc.embedding.len());
+        println!("Content:\n{}", doc.content);
+        return Ok(());
+    }
+
+    let chunks = rag.get_source(id_or_source).await?;
+    if chunks.is_empty() {
+        bail!("No document id or source named '{}'", id_or_source);
+    }
+
+    println!("\n{}", "=".repeat(60));
+    println!("Source: {} ({} chunks)", id_or_source, chunks.len());
+    for doc in chunks {
+        println!("- Chunk {} ID: {}", doc.metadata.index, doc.id.id);
+        println!("  Content preview: {}...", doc.content.chars().take(100).collect::<String>());
+    }
+    Ok(())
+}
+
+pub async fn docs_delete(rag: &RagSystem, target: &DocumentTarget) -> Result<()> {
+    let deleted = rag.delete_documents(target).await?;
+    println!("Deleted {} document chunk(s)", deleted);
+
+    Ok(())
+}
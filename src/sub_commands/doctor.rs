@@ -0,0 +1,328 @@
+use anyhow::{ bail, Result };
+use std::time::Instant;
+
+use crate::rag_system::ollama::has_model;
+use crate::{ Api, Configuration, DbConnection, ModelBackend, OllamaClient, RagSystem };
+
+#[derive(PartialEq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+    Skip,
+}
+
+struct Check {
+    name: String,
+    status: Status,
+    detail: String,
+    hint: Option<String>,
+}
+
+#[derive(Default)]
+struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    fn add(&mut self, name: &str, status: Status, detail: impl Into<String>, hint: Option<String>) {
+        self.checks.push(Check { name: name.to_string(), status, detail: detail.into(), hint });
+    }
+
+    fn pass(&mut self, name: &str, detail: impl Into<String>) {
+        self.add(name, Status::Pass, detail, None);
+    }
+
+    fn fail(&mut self, name: &str, detail: impl Into<String>, hint: impl Into<String>) {
+        self.add(name, Status::Fail, detail, Some(hint.into()));
+    }
+
+    fn skip(&mut self, name: &str, reason: &str) {
+        self.add(name, Status::Skip, reason, None);
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|check| check.status == status).count()
+    }
+
+    fn print(&self) {
+        for check in &self.checks {
+            let label = match check.status {
+                Status::Pass => "PASS",
+                Status::Warn => "WARN",
+                Status::Fail => "FAIL",
+                Status::Skip => "SKIP",
+            };
+            println!("[{}] {:<24} {}", label, check.name, check.detail);
+            if let Some(hint) = &check.hint {
+                println!("       {:<24} hint: {}", "", hint);
+            }
+        }
+        println!(
+            "\n{} passed, {} warning(s), {} failed, {} skipped",
+            self.count(Status::Pass),
+            self.count(Status::Warn),
+            self.count(Status::Fail),
+            self.count(Status::Skip)
+        );
+    }
+}
+
+/// First line of an error with its causes, raw anyhow errors span several lines
+fn describe(error: &anyhow::Error) -> String {
+    format!("{:#}", error).lines().next().unwrap_or_default().to_string()
+}
+
+/// Hint for a failing model request, from the known failure messages of the backend
+fn model_hint(error: &str, model: &str, backend: &ModelBackend) -> String {
+    let api = backend.api();
+    if error.contains("cannot decode batches") || error.contains("does not support generate") {
+        format!("'{}' cannot generate, it is likely an embedding model, set {} to a chat model", model, api.setting("generation_model"))
+    } else if error.contains("401 Unauthorized") {
+        "The server requires an API key, set APP_OPENAI_API_KEY".to_string()
+    } else if error.contains("not found") && api == Api::Ollama {
+        format!("Pull the model with `rigrag models pull {}`", model)
+    } else if error.contains("not found") {
+        format!("Check that {} serves '{}'", backend.url(), model)
+    } else if api == Api::Ollama {
+        "Check the Ollama server logs, the model may not fit in GPU memory".to_string()
+    } else {
+        format!("Check the logs of the server at {}, the model may not fit in GPU memory", backend.url())
+    }
+}
+
+async fn check_database(report: &mut Report, cfg: &Configuration, embedder: &ModelBackend, generator: &ModelBackend) -> Option<RagSystem> {
+    let connection = match DbConnection::from_config(cfg) {
+        Ok(connection) => connection,
+        Err(e) => {
+            report.fail("SurrealDB connection", describe(&e), "Fix the APP_SURREAL_DB_* settings");
+            return None;
+        }
+    };
+    let endpoint = connection.endpoint();
+    let auth = connection.auth_method().to_string();
+    let (embedding_model, embedding_model_dimension) = cfg.embedding_model();
+
+    let rag = match
+        RagSystem::connect(connection, embedder.clone(), generator.clone(), embedding_model, embedding_model_dimension, cfg.generation_model()).await
+    {
+        Ok(rag) => rag,
+        Err(e) => {
+            let detail = describe(&e);
+            let hint = if detail.contains("sign in failed") {
+                format!("Check APP_SURREAL_DB_AUTH ({}), APP_SURREAL_DB_USER and APP_SURREAL_DB_PASS for {}/{}", auth, cfg.surreal_db_ns, cfg.surreal_db_db)
+            } else {
+                format!("Start SurrealDB (`docker compose up -d surrealdb`) and check APP_SURREAL_DB_URL={}", cfg.surreal_db_url)
+            };
+            report.fail("SurrealDB connection", detail, hint);
+            return None;
+        }
+    };
+    report.pass("SurrealDB connection", format!("{} with {} auth, {}/{}", endpoint, auth, cfg.surreal_db_ns, cfg.surreal_db_db));
+
+    match rag.migration_status().await {
+        Ok(status) => {
+            let pending = status.iter().filter(|migration| migration.applied_at.is_none()).count();
+            match rag.list_collections().await {
+                Ok(collections) if collections.is_empty() =>
+                    report.fail("Schema", "no collection initialized", "Run `rigrag init-schema`"),
+                Ok(collections) if pending > 0 =>
+                    report.add(
+                        "Schema",
+                        Status::Warn,
+                        format!("{} pending migration(s), {} collection(s)", pending, collections.len()),
+                        Some("Run `rigrag migrate up` or set APP_AUTO_MIGRATE=true".to_string())
+                    ),
+                Ok(collections) => report.pass("Schema", format!("up to date, {} collection(s)", collections.len())),
+                Err(e) => report.fail("Schema", describe(&e), "Check the database user may read the `collections` table"),
+            }
+        }
+        Err(e) => report.fail("Schema", describe(&e), "Migrations need EDITOR rights on the database, see NOTES.md"),
+    }
+
+    Some(rag)
+}
+
+async fn check_dimensions(report: &mut Report, cfg: &Configuration, embedder: &ModelBackend, rag: Option<&RagSystem>, dimension: usize) {
+    let (embedding_model, embedding_model_dimension) = cfg.embedding_model();
+    if dimension != (embedding_model_dimension as usize) {
+        report.fail(
+            "Embedding dimension",
+            format!("'{}' returns {} dimensions, {} configured", embedding_model, dimension, embedding_model_dimension),
+            format!("Set {}={}", embedder.api().setting("embedding_model_dimension"), dimension)
+        );
+    } else {
+        report.pass("Embedding dimension", format!("'{}' returns {} dimensions", embedding_model, dimension));
+    }
+
+    let Some(rag) = rag else {
+        report.skip("Index dimension", "needs the database connection");
+        return;
+    };
+    let collections = match rag.list_collections().await {
+        Ok(collections) => collections,
+        Err(e) => {
+            report.fail("Index dimension", describe(&e), "Check the database user may read the `collections` table");
+            return;
+        }
+    };
+    for collection in collections {
+        let name = format!("Index '{}'", collection.name);
+        match rag.index_dimension(&collection.table).await {
+            Ok(None) => report.fail(&name, format!("no vector index on {}", collection.table), "Run `rigrag init-schema`"),
+            Ok(Some(index)) if index != collection.dimension =>
+                report.fail(
+                    &name,
+                    format!("index has {} dimensions, collection records {}", index, collection.dimension),
+                    format!("Rebuild it with `rigrag --collection {} reembed --model {} --dimension {}`", collection.name, collection.embedding_model, index)
+                ),
+            Ok(Some(index)) if collection.embedding_model == embedding_model && (index as usize) != dimension =>
+                report.fail(
+                    &name,
+                    format!("index has {} dimensions, '{}' returns {}", index, collection.embedding_model, dimension),
+                    format!("Rebuild it with `rigrag --collection {} reembed --model {} --dimension {}`", collection.name, collection.embedding_model, dimension)
+                ),
+            Ok(Some(index)) => report.pass(&name, format!("{} dimensions, model '{}'", index, collection.embedding_model)),
+            Err(e) => report.fail(&name, describe(&e), "Check the database user may read the table definition"),
+        }
+    }
+}
+
+pub async fn doctor(cfg: &Configuration) -> Result<()> {
+    let mut report = Report::default();
+    report.pass("Configuration", match &cfg.profile {
+        Some(profile) => format!("profile '{}'", profile),
+        None => "defaults and environment".to_string(),
+    });
+
+    let ollama = OllamaClient::from_config(cfg)?;
+    let embedder = ModelBackend::embedder(&ollama, cfg)?;
+    let generator = ModelBackend::generator(&ollama, cfg)?;
+    let rag = check_database(&mut report, cfg, &embedder, &generator).await;
+
+    let (embedding_model, _) = cfg.embedding_model();
+    let generation_model = cfg.generation_model();
+    let roles = [
+        ("Embedding model", embedding_model, &embedder, "APP_OPENAI_EMBEDDING_URL (or APP_OPENAI_URL)"),
+        ("Generation model", generation_model, &generator, "APP_OPENAI_URL"),
+    ];
+    let mut ready = [false, false];
+
+    // Each server of a pool is checked on its own, the test requests below go through the balancing
+    if roles.iter().any(|(_, _, client, _)| client.api() == Api::Ollama) {
+        for endpoint in ollama.endpoints() {
+            let url = endpoint.url();
+            let models = match endpoint.version().await {
+                Ok(version) => {
+                    report.pass("Ollama", format!("{} at {}", version, url));
+                    match endpoint.tags().await {
+                        Ok(models) => Some(models),
+                        Err(e) => {
+                            report.fail("Ollama models", describe(&e), format!("Check the Ollama server logs on {}", url));
+                            None
+                        }
+                    }
+                }
+                Err(e) => {
+                    report.fail("Ollama", describe(&e), format!("Start Ollama (`docker compose up -d ollama`) and check {} in APP_OLLAMA_URL(S)", url));
+                    None
+                }
+            };
+
+            for (index, (name, model, client, _)) in roles.iter().enumerate() {
+                if client.api() != Api::Ollama {
+                    continue;
+                }
+                match &models {
+                    Some(models) if has_model(models, model) => {
+                        report.pass(name, format!("'{}' available on {}", model, url));
+                        ready[index] = true;
+                    }
+                    Some(_) =>
+                        report.fail(
+                            name,
+                            format!("'{}' not pulled on {}", model, url),
+                            format!("Pull it with `rigrag models pull {}` or set APP_OLLAMA_AUTO_PULL=true", model)
+                        ),
+                    None => report.skip(name, "needs Ollama"),
+                }
+            }
+        }
+    }
+
+    // OpenAI-compatible servers often serve a single model under its own name, the configured one is only sent along
+    for (index, (name, model, client, setting)) in roles.iter().enumerate() {
+        if client.api() != Api::OpenAi {
+            continue;
+        }
+        match client.models().await {
+            Ok(models) => {
+                let served = models.join(", ");
+                report.pass(name, format!("'{}' on OpenAI-compatible {}, serving: {}", model, client.url(), served));
+                ready[index] = true;
+            }
+            Err(e) =>
+                report.fail(
+                    name,
+                    describe(&e),
+                    format!("Start the server (e.g. `llama-server -m model.gguf --port 8080`), check {} and APP_OPENAI_API_KEY", setting)
+                ),
+        }
+    }
+    let [embedding_ready, generation_ready] = ready;
+
+    if embedding_ready {
+        match embedder.embedding(embedding_model, "dimension probe").await {
+            Ok(embedding) => check_dimensions(&mut report, cfg, &embedder, rag.as_ref(), embedding.len()).await,
+            Err(e) => {
+                let detail = describe(&e);
+                let hint = model_hint(&detail, embedding_model, &embedder);
+                report.fail("Embedding dimension", detail, hint);
+            }
+        }
+    } else {
+        report.skip("Embedding dimension", "needs the embedding model");
+    }
+
+    if generation_ready {
+        let start = Instant::now();
+        match generator.generate(generation_model, "Reply with the single word OK.").await {
+            Ok(generation) =>
+                report.pass(
+                    "Test generation",
+                    format!("'{}' replied in {} ms: {}", generation_model, start.elapsed().as_millis(), generation.text.trim().chars().take(40).collect::<String>())
+                ),
+            Err(e) => {
+                let detail = describe(&e);
+                let hint = model_hint(&detail, generation_model, &generator);
+                report.fail("Test generation", detail, hint);
+            }
+        }
+    } else {
+        report.skip("Test generation", "needs the generation model");
+    }
+
+    report.print();
+
+    let failed = report.count(Status::Fail);
+    if failed > 0 {
+        bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_hint() {
+        let cfg = Configuration { generation_backend: "openai".to_string(), openai_generation_model: "bge-m3".to_string(), ..Configuration::default() };
+        let generator = ModelBackend::generator(&OllamaClient::from_config(&cfg).unwrap(), &cfg).unwrap();
+
+        let hint = model_hint("400 Bad Request: model does not support generate", "bge-m3", &generator);
+        assert!(hint.contains("APP_OPENAI_GENERATION_MODEL") && !hint.contains("OLLAMA"), "{}", hint);
+        let hint = model_hint("404 Not Found: model not found", "bge-m3", &generator);
+        assert!(!hint.contains("rigrag models pull"), "{}", hint);
+    }
+}
@@ -0,0 +1,14 @@
+use anyhow::Result;
+use std::fs::File;
+use std::io::BufWriter;
+
+use crate::{ RagSystem, RAG_EXPORT_BATCH_SIZE };
+
+pub async fn export(rag: &RagSystem, path: &str) -> Result<()> {
+    // Stream chunks with metadata, embeddings and model info to a JSON Lines file
+    let writer = BufWriter::new(File::create(path)?);
+    let exported = rag.export_jsonl(writer, RAG_EXPORT_BATCH_SIZE).await?;
+    println!("Exported {} chunks from collection '{}' to {}", exported, rag.collection, path);
+
+    Ok(())
+}
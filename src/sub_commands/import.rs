@@ -0,0 +1,14 @@
+use anyhow::Result;
+use std::fs::File;
+use std::io::BufReader;
+
+use crate::{ RagSystem, RAG_EXPORT_BATCH_SIZE };
+
+pub async fn import(rag: &RagSystem, path: &str) -> Result<()> {
+    // Load a JSON Lines export without re-embedding, existing chunks are skipped
+    let reader = BufReader::new(File::open(path)?);
+    let imported = rag.import_jsonl(reader, RAG_EXPORT_BATCH_SIZE).await?;
+    println!("Imported {} chunks into collection '{}' from {}", imported, rag.collection, path);
+
+    Ok(())
+}
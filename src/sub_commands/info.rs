@@ -1,23 +1,33 @@
 use anyhow::Result;
 
-use crate::RagSystem;
+use crate::{ ListOptions, RagSystem };
 
 pub async fn info(rag: &RagSystem) -> Result<()> {
     // Show some system info
     println!("\n{}", "=".repeat(60));
     println!("System Information:");
 
-    let all_docs = rag.list_documents().await?;
-    println!("Total documents in knowledge base: {}", all_docs.len());
+    let total = rag.count_documents().await?;
+    println!("Total documents in knowledge base: {}", total);
 
-    for doc in all_docs {
-        println!("- Document ID: {}", doc.id.id);
-        println!(
-            "  Content preview: {}...",
-            doc.content.chars().take(100).collect::<String>()
-        );
-        println!("  Metadata: {:?}", doc.metadata);
-        println!();
+    // Page through documents without loading embedding vectors
+    let mut options = ListOptions::default();
+    loop {
+        let docs = rag.list_documents(&options).await?;
+        if docs.is_empty() {
+            break;
+        }
+        options.start += docs.len();
+
+        for doc in docs {
+            println!("- Document ID: {}", doc.id.id);
+            println!(
+                "  Content preview: {}...",
+                doc.content.chars().take(100).collect::<String>()
+            );
+            println!("  Metadata: {:?}", doc.metadata);
+            println!();
+        }
     }
     Ok(())
 }
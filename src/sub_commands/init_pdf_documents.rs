@@ -1,40 +1,20 @@
 use anyhow::{ Context, Result };
 use rig::loaders::PdfFileLoader;
-use std::path::PathBuf;
+use std::path::{ Path, PathBuf };
 use tracing::info;
 use std::fs;
 
-use crate::{ rag_system::DocumentMetaData, sanitize_chunk_comprehensive, RagSystem, RAG_CHUNK_SIZE };
+use crate::{ chunk_text, rag_system::DocumentMetaData, sanitize_chunk_comprehensive, RagSystem, UserMetaData, RAG_CHUNK_SIZE };
 
-fn load_pdf(path: PathBuf) -> Result<Vec<String>> {
-    let mut chunks = Vec::new();
-    let mut current_chunk = String::new();
+pub fn load_pdf(path: PathBuf) -> Result<Vec<String>> {
+    let mut content = String::new();
 
     for entry in PdfFileLoader::with_glob(path.to_str().unwrap())?.read() {
-        let content = entry?;
-
-        // Split content into words
-        let words: Vec<&str> = content.split_whitespace().collect();
-
-        for word in words {
-            if current_chunk.len() + word.len() + 1 > RAG_CHUNK_SIZE {
-                // If adding the next word would exceed chunk size,
-                // save current chunk and start a new one
-                if !current_chunk.is_empty() {
-                    chunks.push(current_chunk.trim().to_string());
-                    current_chunk.clear();
-                }
-            }
-            current_chunk.push_str(word);
-            current_chunk.push(' ');
-        }
-    }
-
-    // last chunk
-    if !current_chunk.is_empty() {
-        chunks.push(current_chunk.trim().to_string());
+        content.push_str(&entry?);
+        content.push(' ');
     }
 
+    let chunks = chunk_text(&content, RAG_CHUNK_SIZE);
     if chunks.is_empty() {
         anyhow::bail!("No content found in PDF file: {:?}", path);
     }
@@ -42,13 +22,33 @@ fn load_pdf(path: PathBuf) -> Result<Vec<String>> {
     Ok(chunks)
 }
 
-pub async fn init_pdf_documents(rag: &RagSystem) -> Result<()> {
+// Load user metadata from an optional `<file>.meta.json` sidecar next to the PDF
+fn load_sidecar(path: &Path) -> Result<Option<UserMetaData>> {
+    let sidecar = PathBuf::from(format!("{}.meta.json", path.display()));
+    if !sidecar.exists() {
+        return Ok(None);
+    }
+
+    let file = fs::File::open(&sidecar)?;
+    let user_metadata = serde_json::from_reader(file).with_context(|| format!("Failed to parse sidecar {:?}", sidecar))?;
+    Ok(Some(user_metadata))
+}
+
+pub async fn init_pdf_documents(rag: &RagSystem, user_metadata: &UserMetaData) -> Result<()> {
     let mut documents = Vec::new();
     let paths = fs::read_dir("./documents").unwrap();
     for path in paths {
         match path {
             Ok(p) => {
+                // skip sidecar metadata and other non PDF files
+                if p.path().extension().is_none_or(|extension| !extension.eq_ignore_ascii_case("pdf")) {
+                    continue;
+                }
                 let source = p.file_name().to_string_lossy().to_string();
+                let user_metadata = match load_sidecar(&p.path())? {
+                    Some(sidecar) => user_metadata.merge(&sidecar),
+                    None => user_metadata.clone(),
+                };
                 // info!("file path: {}", p.path().display());
                 let document_vector = load_pdf(p.path().into()).context(
                     "Failed to load Moores_Law_for_Everything.pdf"
@@ -60,10 +60,7 @@ pub async fn init_pdf_documents(rag: &RagSystem) -> Result<()> {
                     // info!("i: {}, chunk: {}", i, sanitized_chunk);
                     documents.push((
                         sanitized_chunk,
-                        DocumentMetaData {
-                            index: i,
-                            source: p.file_name().to_string_lossy().to_string(),
-                        },
+                        DocumentMetaData::new(i, &source, &user_metadata),
                     ));
                 }
             }
@@ -0,0 +1,49 @@
+use anyhow::Result;
+
+use crate::{ ApiScope, RagSystem };
+
+pub async fn keys_create(
+    rag: &RagSystem,
+    name: &str,
+    scopes: Vec<ApiScope>,
+    groups: Vec<String>,
+    tenant: Option<String>,
+    requests_per_day: Option<usize>,
+    tokens_per_day: Option<usize>
+) -> Result<()> {
+    let (key, secret) = rag.create_api_key(name, scopes, groups, tenant, requests_per_day, tokens_per_day).await?;
+    println!("Created API key '{}' with scopes {:?} for tenant {}", key.name, key.scopes, key.tenant.as_deref().unwrap_or("-"));
+    println!("Store it now, it cannot be shown again:\n{}", secret);
+
+    Ok(())
+}
+
+fn quota(quota: Option<usize>) -> String {
+    quota.map(|quota| quota.to_string()).unwrap_or_else(|| "unlimited".to_string())
+}
+
+pub async fn keys_list(rag: &RagSystem) -> Result<()> {
+    let keys = rag.list_api_keys().await?;
+    println!("Total API keys: {}", keys.len());
+
+    for key in keys {
+        println!("- API key: {} ({}...)", key.name, key.prefix);
+        println!("  Scopes: {:?}", key.scopes);
+        println!("  Groups: {:?}", key.groups);
+        println!("  Tenant: {}", key.tenant.as_deref().unwrap_or("-"));
+        println!("  Daily quotas: {} requests, {} tokens", quota(key.requests_per_day), quota(key.tokens_per_day));
+        println!("  Usage on {}: {} requests, {} tokens", key.usage_day.as_deref().unwrap_or("-"), key.requests, key.tokens);
+        println!("  Created at: {}", key.created_at);
+        if let Some(revoked_at) = &key.revoked_at {
+            println!("  Revoked at: {}", revoked_at);
+        }
+    }
+    Ok(())
+}
+
+pub async fn keys_revoke(rag: &RagSystem, name: &str) -> Result<()> {
+    rag.revoke_api_key(name).await?;
+    println!("Revoked API key '{}'", name);
+
+    Ok(())
+}
@@ -0,0 +1,7 @@
+use anyhow::Result;
+
+use crate::RagSystem;
+
+pub async fn mcp(rag: &RagSystem) -> Result<()> {
+    crate::mcp::serve_stdio(rag.clone()).await
+}
@@ -0,0 +1,11 @@
+use anyhow::Result;
+
+use crate::{ DocumentTarget, MetadataUpdate, RagSystem };
+
+pub async fn metadata(rag: &RagSystem, target: &DocumentTarget, update: &MetadataUpdate) -> Result<()> {
+    // Edit tags and attributes of already stored documents
+    let updated = rag.update_metadata(target, update).await?;
+    println!("Updated metadata of {} document chunk(s)", updated);
+
+    Ok(())
+}
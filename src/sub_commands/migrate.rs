@@ -0,0 +1,35 @@
+use anyhow::Result;
+
+use crate::{ latest_version, RagSystem };
+
+pub async fn migrate_up(rag: &RagSystem) -> Result<()> {
+    let versions = rag.migrate_up().await?;
+    if versions.is_empty() {
+        println!("No pending migrations");
+    }
+    for version in versions {
+        println!("Applied migration {}", version);
+    }
+
+    Ok(())
+}
+
+pub async fn migrate_status(rag: &RagSystem) -> Result<()> {
+    println!("\n{}", "=".repeat(60));
+    let migrations = rag.migration_status().await?;
+    let current = migrations
+        .iter()
+        .filter(|migration| migration.applied_at.is_some())
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or_default();
+    println!("Schema version: {} (latest: {})", current, latest_version());
+
+    for migration in migrations {
+        match migration.applied_at {
+            Some(applied_at) => println!("- [x] {:>3} {} (applied at {})", migration.version, migration.name, applied_at),
+            None => println!("- [ ] {:>3} {} (pending)", migration.version, migration.name),
+        }
+    }
+    Ok(())
+}
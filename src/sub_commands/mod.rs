@@ -1,13 +1,45 @@
+mod backup;
+mod collection;
+mod docs;
+mod doctor;
+mod export;
+mod import;
 mod info;
 mod init_documents;
 mod init_pdf_documents;
 mod init_schema;
+mod keys;
+mod mcp;
+mod metadata;
+mod migrate;
+mod models;
 mod query_documents;
 mod query_pdf_documents;
+mod reembed;
+mod serve;
+mod show_config;
+mod stats;
+mod tenant;
 
+pub use backup::{ backup, restore };
+pub use collection::{ collection_create, collection_drop, collection_list };
+pub use docs::{ docs_delete, docs_list, docs_show };
+pub use doctor::doctor;
+pub use export::export;
+pub use import::import;
 pub use info::info;
 pub use init_documents::init_documents;
-pub use init_pdf_documents::init_pdf_documents;
+pub use init_pdf_documents::{ init_pdf_documents, load_pdf };
 pub use init_schema::init_schema;
+pub use keys::{ keys_create, keys_list, keys_revoke };
+pub use mcp::mcp;
+pub use metadata::metadata;
+pub use migrate::{ migrate_status, migrate_up };
+pub use models::{ models_list, models_pull, models_show, pull_missing_models };
 pub use query_documents::query as query_documents;
-pub use query_pdf_documents::query as query_pdf_documents;
\ No newline at end of file
+pub use query_pdf_documents::query as query_pdf_documents;
+pub use reembed::reembed;
+pub use serve::serve;
+pub use show_config::show_config;
+pub use stats::stats;
+pub use tenant::{ tenant_create, tenant_drop, tenant_list };
\ No newline at end of file
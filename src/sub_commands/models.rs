@@ -0,0 +1,107 @@
+use anyhow::Result;
+use futures::StreamExt;
+use std::io::Write;
+use tracing::info;
+
+use crate::rag_system::ollama::has_model;
+use crate::{ human_bytes, Configuration, OllamaClient };
+
+/// Pulls a model, printing Ollama's progress on stderr
+async fn pull_model(ollama: &OllamaClient, model: &str) -> Result<()> {
+    let mut progress = ollama.pull(model).await?;
+    let mut downloading = false;
+    let mut last_status = String::new();
+
+    while let Some(line) = progress.next().await {
+        let line = line?;
+        match (line.total, line.completed) {
+            (Some(total), Some(completed)) if total > 0 => {
+                eprint!("\r{}: {} / {} ({:.0}%)", line.status, human_bytes(completed), human_bytes(total), ((completed as f64) * 100.0) / (total as f64));
+                std::io::stderr().flush()?;
+                downloading = true;
+            }
+            _ if line.status != last_status => {
+                if downloading {
+                    eprintln!();
+                    downloading = false;
+                }
+                eprintln!("{}", line.status);
+            }
+            _ => {}
+        }
+        last_status = line.status;
+    }
+    if downloading {
+        eprintln!();
+    }
+
+    info!("Pulled model '{}'", model);
+    Ok(())
+}
+
+pub async fn models_list(cfg: &Configuration) -> Result<()> {
+    for ollama in OllamaClient::from_config(cfg)?.endpoints() {
+        let models = ollama.tags().await?;
+
+        println!("Models on {}:", ollama.url());
+        for model in &models {
+            let mut roles = Vec::new();
+            if has_model(std::slice::from_ref(model), &cfg.ollama_embedding_model) {
+                roles.push("embedding");
+            }
+            if has_model(std::slice::from_ref(model), &cfg.ollama_generation_model) {
+                roles.push("generation");
+            }
+            let roles = if roles.is_empty() { String::new() } else { format!(" [{}]", roles.join(", ")) };
+            println!("- {}: {}, modified {}{}", model.name, human_bytes(model.size), model.modified_at, roles);
+        }
+        for (role, name) in [("embedding", &cfg.ollama_embedding_model), ("generation", &cfg.ollama_generation_model)] {
+            if !has_model(&models, name) && cfg.ollama_models().contains(name) {
+                println!("Configured {} model '{}' is not pulled, run `rigrag models pull {}`", role, name, name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Pulls the given models, or the configured models served by Ollama, on every Ollama server
+pub async fn models_pull(cfg: &Configuration, models: &[String]) -> Result<()> {
+    let configured = cfg.ollama_models();
+    let models = if models.is_empty() { &configured[..] } else { models };
+
+    for ollama in OllamaClient::from_config(cfg)?.endpoints() {
+        for model in models {
+            info!("Pulling model '{}' on {}", model, ollama.url());
+            pull_model(&ollama, model).await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn models_show(cfg: &Configuration, model: &str) -> Result<()> {
+    let info = OllamaClient::from_config(cfg)?.show(model).await?;
+
+    println!("Model: {}", info.name);
+    println!("Family: {}", info.family);
+    println!("Parameters: {}", info.parameter_size);
+    println!("Quantization: {}", info.quantization_level);
+    println!("Context length: {}", info.context_length.map(|length| length.to_string()).unwrap_or("-".to_string()));
+    println!("num_ctx: {}", info.num_ctx.map(|length| length.to_string()).unwrap_or("default".to_string()));
+    println!("Context window used for prompts: {} tokens", info.context_window());
+    Ok(())
+}
+
+/// Pulls the configured models served by Ollama where they are missing
+pub async fn pull_missing_models(ollama: &OllamaClient, cfg: &Configuration) -> Result<()> {
+    for ollama in ollama.endpoints() {
+        let available = ollama.tags().await?;
+
+        for model in cfg.ollama_models() {
+            if !has_model(&available, &model) {
+                info!("Model '{}' is missing on {}, pulling it", model, ollama.url());
+                pull_model(&ollama, &model).await?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,8 +1,8 @@
 use anyhow::Result;
 
-use crate::RagSystem;
+use crate::{ QueryOptions, RagSystem };
 
-pub async fn query(rag: &RagSystem) -> Result<()> {
+pub async fn query(rag: &RagSystem, options: &QueryOptions) -> Result<()> {
     // Example queries
     let queries = vec![
         "What is Rust programming language?",
@@ -17,7 +17,7 @@ pub async fn query(rag: &RagSystem) -> Result<()> {
         println!("\n{}", "=".repeat(60));
         println!("Question: {}", query);
 
-        match rag.query(query).await {
+        match rag.query_with_options(query, options).await {
             Ok(answer) => println!("Answer: {}", answer),
             Err(e) => println!("Error: {}", e),
         }
@@ -1,8 +1,8 @@
 use anyhow::Result;
 
-use crate::RagSystem;
+use crate::{ QueryOptions, RagSystem };
 
-pub async fn query(rag: &RagSystem) -> Result<()> {
+pub async fn query(rag: &RagSystem, options: &QueryOptions) -> Result<()> {
     // Example queries: https://chatgpt.com/c/688107d4-8248-8322-984a-d3afe03fd5a2
     let queries = vec![
         "What is the central thesis of \"Moore’s Law for Everything\"?",
@@ -32,7 +32,7 @@ pub async fn query(rag: &RagSystem) -> Result<()> {
         println!("\n{}", "=".repeat(60));
         println!("Question: {}", query);
 
-        match rag.query(query).await {
+        match rag.query_with_options(query, options).await {
             Ok(answer) => println!("Answer: {}", answer),
             Err(e) => println!("Error: {}", e),
         }
@@ -0,0 +1,17 @@
+use anyhow::Result;
+
+use crate::RagSystem;
+
+pub async fn reembed(rag: &RagSystem, embedding_model: &str, embedding_model_dimension: u16, batch_size: usize, force: bool) -> Result<()> {
+    // Re-embed stored chunk content with the new model, the old table is kept until the swap
+    let collection = rag.reembed(embedding_model, embedding_model_dimension, batch_size, force).await?;
+    println!(
+        "Collection '{}' now uses embedding model '{}' ({} dimensions) in table {}",
+        collection.name,
+        collection.embedding_model,
+        collection.dimension,
+        collection.table
+    );
+
+    Ok(())
+}
@@ -0,0 +1,7 @@
+use anyhow::Result;
+
+use crate::RagSystem;
+
+pub async fn serve(rag: &RagSystem, addr: &str, auth: bool) -> Result<()> {
+    crate::server::serve(rag.clone(), addr, auth).await
+}
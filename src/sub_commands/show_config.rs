@@ -0,0 +1,5 @@
+use crate::Configuration;
+
+pub fn show_config(cfg: &Configuration) {
+    println!("{:#?}", cfg);
+}
@@ -0,0 +1,43 @@
+use anyhow::{ bail, Result };
+
+use crate::{ human_bytes, RagSystem };
+
+pub async fn stats(rag: &RagSystem, format: &str) -> Result<()> {
+    let stats = rag.stats().await?;
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&stats)?),
+        "text" => {
+            println!("\n{}", "=".repeat(60));
+            println!("Knowledge base statistics for collection '{}' (table: {})", stats.collection, stats.table);
+            println!("Embedding model: {} ({} dimensions)", stats.embedding_model, stats.dimension);
+            println!("Vector index: {}", stats.index.as_deref().unwrap_or("none"));
+            println!("Chunks: {} from {} sources", stats.chunks, stats.sources.len());
+            println!("Total characters: {}", stats.total_characters);
+            println!(
+                "Chunk length: avg {:.1}, min {}, max {}",
+                stats.avg_chunk_length,
+                stats.min_chunk_length,
+                stats.max_chunk_length
+            );
+            println!(
+                "Created: {} .. {}",
+                stats.first_created_at.as_deref().unwrap_or("-"),
+                stats.last_created_at.as_deref().unwrap_or("-")
+            );
+            println!(
+                "Estimated storage: {} (content {}, embeddings {}, index {})",
+                human_bytes(stats.estimated_total_bytes),
+                human_bytes(stats.estimated_content_bytes),
+                human_bytes(stats.estimated_embedding_bytes),
+                human_bytes(stats.estimated_index_bytes)
+            );
+            println!("Sources:");
+            for source in &stats.sources {
+                println!("- {}: {} chunks, {} characters", source.source, source.chunks, source.characters);
+            }
+        }
+        other => bail!("Unknown format '{}', expected text or json", other),
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use anyhow::Result;
+
+use crate::RagSystem;
+
+pub async fn tenant_create(rag: &RagSystem, name: &str, namespace: Option<&str>, database: Option<&str>) -> Result<()> {
+    let tenant = rag.create_tenant(name, namespace, database).await?;
+    println!("Created tenant '{}' (namespace: {}, database: {})", tenant.name, tenant.namespace, tenant.database);
+
+    Ok(())
+}
+
+pub async fn tenant_list(rag: &RagSystem) -> Result<()> {
+    let tenants = rag.list_tenants().await?;
+    println!("Total tenants: {}", tenants.len());
+
+    for tenant in tenants {
+        println!("- Tenant: {}", tenant.name);
+        println!("  Namespace/database: {}/{}", tenant.namespace, tenant.database);
+        println!("  Created at: {}", tenant.created_at);
+    }
+    Ok(())
+}
+
+pub async fn tenant_drop(rag: &RagSystem, name: &str) -> Result<()> {
+    rag.drop_tenant(name).await?;
+    println!("Dropped tenant '{}' and its database", name);
+
+    Ok(())
+}
@@ -13,3 +13,31 @@ pub fn save_json_file(documents: &Vec<(String, DocumentMetaData)>) -> Result<()>
     serde_json::to_writer_pretty(&mut file, &documents)?;
     Ok(())
 }
+
+/// Byte count in binary units, e.g. `1.5 GiB`
+pub fn human_bytes(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, units[unit])
+}
+
+/// Record key of a document id, accepting both `uuid` and `table:⟨uuid⟩`
+pub fn record_key(id: &str) -> &str {
+    id.rsplit_once(':').map(|(_, key)| key).unwrap_or(id).trim_matches(['⟨', '⟩'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_key() {
+        assert_eq!(record_key("5f0c"), "5f0c");
+        assert_eq!(record_key("documents:⟨5f0c-1a⟩"), "5f0c-1a");
+    }
+}